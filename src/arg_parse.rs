@@ -17,15 +17,60 @@ pub mod args {
 
     fn print_usage_and_exit(program: &str) -> ! {
         eprintln!(
-            "Usage: {prog} [--palette <a> <b> <c> <d>] [--rom_file]
+            "Usage: {prog} [--palette <a> <b> <c> <d>] [--rom_file] [--info]
+            [--rewind-budget <MiB>] [--rewind-interval <frames>]
+            [--headless (--frames <n> | --cycles <n>) [--dump-framebuffer <file>] [--dump-memory <file>]]
+            [--record-audio <file> [--record-stems]]
+            [--gbs <file> [--track <n>]... [--seconds <n>]]
+            [--serial-stdout] [--test-result]
+            [--link-listen <[host:]port> | --link-connect <host:port> | --link-rom <file> | --printer <dir>]
+            [--debug | --gdb <port>]
   --palette   four u32 values (decimal, 0xhex, or plain hex digits)
   --rom_file    optional positional ROM file path
+  --info      print the cartridge header of the ROM and exit
+  --rewind-budget    memory used for rewind history in MiB, 0 disables it (default 32)
+  --rewind-interval  frames between rewind snapshots (default 2)
+  --headless  run without a window, then dump the framebuffer and memory
+  --frames    frames to run in headless mode
+  --cycles    dot cycles to run in headless mode, after --frames
+  --dump-framebuffer  PGM file for the final framebuffer (default framebuffer.pgm)
+  --dump-memory       file for the final 64 KiB address space (default memory.bin)
+  --record-audio  write the stereo output to a 16-bit WAV file, at the playback
+                  device's rate (48 kHz headless)
+  --record-stems  also write every channel to <file>.ch1.wav to <file>.ch4.wav
+  --gbs       render the tracks of a GBS sound file to <file>-NN.wav, or to the
+              --record-audio file when a single track is selected
+  --track     1-based track to render, can be repeated (default every track)
+  --seconds   length of each rendered track (default 150)
+  --serial-stdout  print the bytes sent over the link port to stdout
+  --test-result    stop once the serial output says Passed or Failed and exit with 0 or 1,
+                   3 if neither showed up
+  --link-listen   wait for a second rustemu on this TCP port and link the two Game Boys, only
+                  local connections unless a host like 0.0.0.0 is given
+  --link-connect  link to a rustemu waiting with --link-listen
+  --link-rom      run a second Game Boy with this ROM next to the first one, linked to it
+  --printer       plug in a Game Boy Printer that saves its pages as PNG files in this directory
+  --debug     start in the debugger on stdin, F12 breaks into it while running
+  --gdb       wait for a GDB remote protocol client on this local port and let it drive the CPU
   -h, --help  show this message",
             prog = program
         );
         process::exit(2);
     }
 
+    fn parse_value<T: std::str::FromStr>(parser: &mut Parser, flag: &str) -> Result<T, String>
+    where
+        T::Err: std::fmt::Display,
+    {
+        parser
+            .value()
+            .map_err(|e| e.to_string())?
+            .string()
+            .map_err(|e| e.to_string())?
+            .parse()
+            .map_err(|e| format!("invalid --{}: {}", flag, e))
+    }
+
     fn parse_palette(parser: &mut Parser) -> Result<[u32; 4], String> {
         let mut vals = [0u32; 4];
         for i in 0..4 {
@@ -39,12 +84,59 @@ pub mod args {
         Ok(vals)
     }
 
-    pub fn parse_args() -> Result<(Option<[u32; 4]>, Option<String>), String> {
+    pub struct Args {
+        pub palette: Option<[u32; 4]>,
+        pub rom_file: Option<String>,
+        pub info: bool,
+        pub rewind_budget_bytes: Option<usize>,
+        pub rewind_interval: Option<u64>,
+        pub headless: bool,
+        pub frames: Option<u64>,
+        pub cycles: Option<u64>,
+        pub dump_framebuffer: Option<String>,
+        pub dump_memory: Option<String>,
+        pub record_audio: Option<String>,
+        pub record_stems: bool,
+        pub gbs: Option<String>,
+        pub tracks: Vec<u8>,
+        pub seconds: Option<u64>,
+        pub serial_stdout: bool,
+        pub test_result: bool,
+        pub link_listen: Option<String>,
+        pub link_connect: Option<String>,
+        pub link_rom: Option<String>,
+        pub printer: Option<String>,
+        pub debug: bool,
+        pub gdb: Option<u16>,
+    }
+
+    pub fn parse_args() -> Result<Args, String> {
         let mut parser = Parser::from_env();
         let program = std::env::args().next().unwrap_or_else(|| "program".into());
 
         let mut palette: Option<[u32; 4]> = None;
         let mut rom_file: Option<String> = None;
+        let mut info = false;
+        let mut rewind_budget_bytes: Option<usize> = None;
+        let mut rewind_interval: Option<u64> = None;
+        let mut headless = false;
+        let mut frames: Option<u64> = None;
+        let mut cycles: Option<u64> = None;
+        let mut dump_framebuffer: Option<String> = None;
+        let mut dump_memory: Option<String> = None;
+        let mut record_audio: Option<String> = None;
+        let mut record_stems = false;
+        let mut gbs: Option<String> = None;
+        let mut tracks: Vec<u8> = Vec::new();
+        let mut seconds: Option<u64> = None;
+        let mut serial_stdout = false;
+        let mut test_result = false;
+        let mut link_listen: Option<String> = None;
+        let mut link_connect: Option<String> = None;
+        let mut link_rom: Option<String> = None;
+        let mut printer: Option<String> = None;
+        let mut debug = false;
+        let mut gdb: Option<u16> = None;
 
         while let Some(arg) = parser.next().map_err(|e| e.to_string())? {
             match arg {
@@ -55,6 +147,53 @@ pub mod args {
                     palette = Some(parse_palette(&mut parser)?);
                 }
                 Short('h') | Long("help") => print_usage_and_exit(&program),
+                Long("info") => info = true,
+                Long("rewind-budget") => {
+                    let budget_mib: usize = parse_value(&mut parser, "rewind-budget")?;
+                    let budget_bytes = budget_mib
+                        .checked_mul(1024 * 1024)
+                        .ok_or("--rewind-budget is out of range")?;
+                    rewind_budget_bytes = Some(budget_bytes);
+                }
+                Long("rewind-interval") => {
+                    let interval: u64 = parse_value(&mut parser, "rewind-interval")?;
+                    if interval == 0 {
+                        return Err("--rewind-interval must be at least 1".into());
+                    }
+                    rewind_interval = Some(interval);
+                }
+                Long("headless") => headless = true,
+                Long("frames") => frames = Some(parse_value(&mut parser, "frames")?),
+                Long("cycles") => cycles = Some(parse_value(&mut parser, "cycles")?),
+                Long("dump-framebuffer") => {
+                    dump_framebuffer = Some(parse_value(&mut parser, "dump-framebuffer")?);
+                }
+                Long("dump-memory") => {
+                    dump_memory = Some(parse_value(&mut parser, "dump-memory")?);
+                }
+                Long("record-audio") => {
+                    record_audio = Some(parse_value(&mut parser, "record-audio")?);
+                }
+                Long("record-stems") => record_stems = true,
+                Long("gbs") => gbs = Some(parse_value(&mut parser, "gbs")?),
+                Long("track") => {
+                    let track: u8 = parse_value(&mut parser, "track")?;
+                    if track == 0 {
+                        return Err("--track counts from 1".into());
+                    }
+                    tracks.push(track);
+                }
+                Long("seconds") => seconds = Some(parse_value(&mut parser, "seconds")?),
+                Long("serial-stdout") => serial_stdout = true,
+                Long("test-result") => test_result = true,
+                Long("link-listen") => link_listen = Some(parse_value(&mut parser, "link-listen")?),
+                Long("link-connect") => {
+                    link_connect = Some(parse_value(&mut parser, "link-connect")?);
+                }
+                Long("link-rom") => link_rom = Some(parse_value(&mut parser, "link-rom")?),
+                Long("printer") => printer = Some(parse_value(&mut parser, "printer")?),
+                Long("debug") => debug = true,
+                Long("gdb") => gdb = Some(parse_value(&mut parser, "gdb")?),
                 Long("rom_file") => {
                     if rom_file.is_some() {
                         return Err("--rom_file specified multiple times".into());
@@ -72,7 +211,58 @@ pub mod args {
             }
         }
 
-        Ok((palette, rom_file))
+        if record_stems && record_audio.is_none() {
+            return Err("--record-stems needs --record-audio".into());
+        }
+        if gbs.is_none() && (!tracks.is_empty() || seconds.is_some()) {
+            return Err("--track and --seconds need --gbs".into());
+        }
+        let links = [
+            link_listen.is_some(),
+            link_connect.is_some(),
+            link_rom.is_some(),
+            printer.is_some(),
+        ];
+        if links.iter().filter(|&&link| link).count() > 1 {
+            return Err(
+                "--link-listen, --link-connect, --link-rom and --printer exclude each other".into(),
+            );
+        }
+        if links.contains(&true) && (serial_stdout || test_result) {
+            return Err("--serial-stdout and --test-result need the link port to themselves".into());
+        }
+        if debug && gdb.is_some() {
+            return Err("--debug and --gdb exclude each other".into());
+        }
+        if (debug || gdb.is_some()) && link_rom.is_some() {
+            return Err("--debug and --gdb need a single Game Boy, not --link-rom".into());
+        }
+
+        Ok(Args {
+            palette,
+            rom_file,
+            info,
+            rewind_budget_bytes,
+            rewind_interval,
+            headless,
+            frames,
+            cycles,
+            dump_framebuffer,
+            dump_memory,
+            record_audio,
+            record_stems,
+            gbs,
+            tracks,
+            seconds,
+            serial_stdout,
+            test_result,
+            link_listen,
+            link_connect,
+            link_rom,
+            printer,
+            debug,
+            gdb,
+        })
     }
 }
 
@@ -1,8 +0,0 @@
-#[derive(Default)]
-pub struct Audio {}
-
-impl Audio {
-    pub fn new() -> Self {
-        Self::default()
-    }
-}
@@ -0,0 +1,421 @@
+use crate::console::audio::noise::Noise;
+use crate::console::audio::pulse::Pulse;
+use crate::console::audio::wave::Wave;
+use crate::console::constants::*;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const PULSE_1_BEGIN: u16 = 0xFF10;
+const PULSE_2_BEGIN: u16 = 0xFF15;
+const WAVE_BEGIN: u16 = 0xFF1A;
+const NOISE_BEGIN: u16 = 0xFF1F;
+const NR50: u16 = 0xFF24;
+const NR51: u16 = 0xFF25;
+const NR52: u16 = 0xFF26;
+
+// Bits that always read as 1 from NR10 up to 0xFF2F, covering write only bits and unused addresses
+const READ_MASKS: [u8; 0x20] = [
+    0x80, 0x3F, 0x00, 0xFF, 0xBF, // NR10-NR14
+    0xFF, 0x3F, 0x00, 0xFF, 0xBF, // NR20-NR24
+    0x7F, 0xFF, 0x9F, 0xFF, 0xBF, // NR30-NR34
+    0xFF, 0xFF, 0x00, 0x00, 0xBF, // NR40-NR44
+    0x00, 0x00, 0x70, // NR50-NR52
+    0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+];
+
+// The frame sequencer steps on the falling edge of this DIV bit, at 512 Hz
+const FRAME_SEQUENCER_DIV_BIT: u8 = 0x10;
+const M_CYCLES_PER_SECOND: u64 = DOT_CYCLES_PER_SECOND / 4;
+// Per M-cycle charge factor of the output capacitor, which filters out the DC offset of the DACs
+const HIGH_PASS_CHARGE: f32 = 0.999_832;
+
+pub struct Audio {
+    powered: bool,
+    pulse_1: Pulse,
+    pulse_2: Pulse,
+    wave: Wave,
+    noise: Noise,
+    nr50: u8,
+    nr51: u8,
+    // Next frame sequencer step, lengths are clocked on even steps, the sweep on 2 and 6 and
+    // the envelopes on 7
+    frame_step: u8,
+    div_bit: bool,
+    dots: u8,
+
+    sample_rate: u32,
+    // Output samples are box filtered averages of the M-cycles since the previous one
+    sample_phase: u64,
+    accumulator: [f32; 2],
+    accumulated: u32,
+    capacitor: [f32; 2],
+    // Mixed since the last frame, capped at a second when nobody collects them
+    samples: Vec<[f32; 2]>,
+
+    // Per channel output before panning and master volume, only recorded when asked for
+    capture_channels: bool,
+    channel_accumulator: [f32; 4],
+    channel_capacitor: [f32; 4],
+    channel_samples: Vec<[f32; 4]>,
+}
+
+impl Audio {
+    pub fn new() -> Self {
+        Self {
+            powered: false,
+            pulse_1: Pulse::new(true),
+            pulse_2: Pulse::new(false),
+            wave: Wave::new(),
+            noise: Noise::new(),
+            nr50: 0,
+            nr51: 0,
+            frame_step: 0,
+            div_bit: false,
+            dots: 0,
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            sample_phase: 0,
+            accumulator: [0.0; 2],
+            accumulated: 0,
+            capacitor: [0.0; 2],
+            samples: Vec::new(),
+            capture_channels: false,
+            channel_accumulator: [0.0; 4],
+            channel_capacitor: [0.0; 4],
+            channel_samples: Vec::new(),
+        }
+    }
+
+    /// Takes effect from the next sample on, the resampler keeps its phase so the rate can be
+    /// adjusted while playing
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = sample_rate.max(1);
+    }
+
+    pub fn set_capture_channels(&mut self, capture_channels: bool) {
+        self.capture_channels = capture_channels;
+        self.channel_accumulator = [0.0; 4];
+        self.channel_samples.clear();
+    }
+
+    pub fn samples(&self) -> &[[f32; 2]] {
+        &self.samples
+    }
+
+    /// Pulse 1, pulse 2, wave and noise, at the same rate and count as `samples`
+    pub fn channel_samples(&self) -> &[[f32; 4]] {
+        &self.channel_samples
+    }
+
+    pub fn clear_samples(&mut self) {
+        self.samples.clear();
+        self.channel_samples.clear();
+    }
+
+    pub fn read_register(&self, addr: u16) -> u8 {
+        if addr >= WAVE_RAM_BEGIN {
+            return self.wave.ram[(addr - WAVE_RAM_BEGIN) as usize];
+        }
+
+        let value = match addr {
+            PULSE_1_BEGIN..PULSE_2_BEGIN => self.pulse_1.read((addr - PULSE_1_BEGIN) as usize),
+            PULSE_2_BEGIN..WAVE_BEGIN => self.pulse_2.read((addr - PULSE_2_BEGIN) as usize),
+            WAVE_BEGIN..NOISE_BEGIN => self.wave.read((addr - WAVE_BEGIN) as usize),
+            NOISE_BEGIN..NR50 => self.noise.read((addr - NOISE_BEGIN) as usize),
+            NR50 => self.nr50,
+            NR51 => self.nr51,
+            NR52 => {
+                ((self.powered as u8) << 7)
+                    | ((self.noise.enabled() as u8) << 3)
+                    | ((self.wave.enabled() as u8) << 2)
+                    | ((self.pulse_2.enabled() as u8) << 1)
+                    | self.pulse_1.enabled() as u8
+            }
+            _ => 0,
+        };
+        value | READ_MASKS[(addr - AUDIO_REGS_BEGIN) as usize]
+    }
+
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        if addr >= WAVE_RAM_BEGIN {
+            self.wave.ram[(addr - WAVE_RAM_BEGIN) as usize] = value;
+            return;
+        }
+        if addr == NR52 {
+            self.set_power(value & 0x80 != 0);
+            return;
+        }
+
+        if !self.powered {
+            // Only the length counters can be loaded while the APU is off
+            match addr {
+                0xFF11 => self.pulse_1.length.load((value & 0x3F) as u16),
+                0xFF16 => self.pulse_2.length.load((value & 0x3F) as u16),
+                0xFF1B => self.wave.length.load(value as u16),
+                0xFF20 => self.noise.length.load((value & 0x3F) as u16),
+                _ => {}
+            }
+            return;
+        }
+
+        let next_step_clocks_length = self.frame_step.is_multiple_of(2);
+        match addr {
+            PULSE_1_BEGIN..PULSE_2_BEGIN => {
+                let reg = (addr - PULSE_1_BEGIN) as usize;
+                self.pulse_1.write(reg, value, next_step_clocks_length)
+            }
+            PULSE_2_BEGIN..WAVE_BEGIN => {
+                let reg = (addr - PULSE_2_BEGIN) as usize;
+                self.pulse_2.write(reg, value, next_step_clocks_length)
+            }
+            WAVE_BEGIN..NOISE_BEGIN => {
+                let reg = (addr - WAVE_BEGIN) as usize;
+                self.wave.write(reg, value, next_step_clocks_length)
+            }
+            NOISE_BEGIN..NR50 => {
+                let reg = (addr - NOISE_BEGIN) as usize;
+                self.noise.write(reg, value, next_step_clocks_length)
+            }
+            NR50 => self.nr50 = value,
+            NR51 => self.nr51 = value,
+            _ => {}
+        }
+    }
+
+    fn set_power(&mut self, powered: bool) {
+        if self.powered && !powered {
+            self.pulse_1.power_off();
+            self.pulse_2.power_off();
+            self.wave.power_off();
+            self.noise.power_off();
+            self.nr50 = 0;
+            self.nr51 = 0;
+        } else if !self.powered && powered {
+            self.frame_step = 0;
+        }
+        self.powered = powered;
+    }
+
+    fn step_frame_sequencer(&mut self) {
+        if self.frame_step.is_multiple_of(2) {
+            self.pulse_1.clock_length();
+            self.pulse_2.clock_length();
+            self.wave.clock_length();
+            self.noise.clock_length();
+        }
+        if self.frame_step == 2 || self.frame_step == 6 {
+            self.pulse_1.clock_sweep();
+        }
+        if self.frame_step == 7 {
+            self.pulse_1.clock_envelope();
+            self.pulse_2.clock_envelope();
+            self.noise.clock_envelope();
+        }
+        self.frame_step = (self.frame_step + 1) % 8;
+    }
+
+    /// Advances one dot cycle, `div` is the current value of the DIV register
+    pub fn tick(&mut self, div: u8) {
+        let div_bit = div & FRAME_SEQUENCER_DIV_BIT != 0;
+        if self.div_bit && !div_bit && self.powered {
+            self.step_frame_sequencer();
+        }
+        self.div_bit = div_bit;
+
+        // The channels only change on M-cycle boundaries
+        self.dots += 1;
+        if self.dots < 4 {
+            return;
+        }
+        self.dots = 0;
+
+        if self.powered {
+            self.pulse_1.tick(4);
+            self.pulse_2.tick(4);
+            self.wave.tick(4);
+            self.noise.tick(4);
+        }
+        self.mix();
+    }
+
+    fn mix(&mut self) {
+        let outputs = [
+            self.pulse_1.output(),
+            self.pulse_2.output(),
+            self.wave.output(),
+            self.noise.output(),
+        ];
+        let mut left = 0.0;
+        let mut right = 0.0;
+        for (channel, output) in outputs.into_iter().enumerate() {
+            if self.nr51 & (0x10 << channel) != 0 {
+                left += output;
+            }
+            if self.nr51 & (0x01 << channel) != 0 {
+                right += output;
+            }
+        }
+        // NR50 scales each side by 1/8 to 8/8, the four channels share the output range
+        let left = left * (((self.nr50 >> 4) & 0x07) + 1) as f32 / 32.0;
+        let right = right * ((self.nr50 & 0x07) + 1) as f32 / 32.0;
+
+        for (side, input) in [left, right].into_iter().enumerate() {
+            let output = input - self.capacitor[side];
+            self.capacitor[side] = input - output * HIGH_PASS_CHARGE;
+            self.accumulator[side] += output;
+        }
+        if self.capture_channels {
+            for (channel, input) in outputs.into_iter().enumerate() {
+                let output = input - self.channel_capacitor[channel];
+                self.channel_capacitor[channel] = input - output * HIGH_PASS_CHARGE;
+                self.channel_accumulator[channel] += output;
+            }
+        }
+        self.accumulated += 1;
+
+        self.sample_phase += self.sample_rate as u64;
+        if self.sample_phase >= M_CYCLES_PER_SECOND {
+            self.sample_phase -= M_CYCLES_PER_SECOND;
+            let count = self.accumulated as f32;
+            if self.samples.len() < self.sample_rate as usize {
+                self.samples
+                    .push([self.accumulator[0] / count, self.accumulator[1] / count]);
+                if self.capture_channels {
+                    self.channel_samples
+                        .push(self.channel_accumulator.map(|sum| sum / count));
+                }
+            }
+            self.accumulator = [0.0; 2];
+            self.channel_accumulator = [0.0; 4];
+            self.accumulated = 0;
+        }
+    }
+
+    /// The output filter and resampler are left out, they only affect the next few samples
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.powered);
+        self.pulse_1.save_state(state);
+        self.pulse_2.save_state(state);
+        self.wave.save_state(state);
+        self.noise.save_state(state);
+        state.write_u8(self.nr50);
+        state.write_u8(self.nr51);
+        state.write_u8(self.frame_step);
+        state.write_bool(self.div_bit);
+        state.write_u8(self.dots);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.powered = state.read_bool()?;
+        self.pulse_1.load_state(state)?;
+        self.pulse_2.load_state(state)?;
+        self.wave.load_state(state)?;
+        self.noise.load_state(state)?;
+        self.nr50 = state.read_u8()?;
+        self.nr51 = state.read_u8()?;
+        self.frame_step = state.read_u8()? % 8;
+        self.div_bit = state.read_bool()?;
+        self.dots = state.read_u8()? % 4;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::audio::apu::*;
+
+    // Runs the APU with a DIV register that counts like the real one
+    fn run(audio: &mut Audio, div: &mut u16, dots: u64) {
+        for _ in 0..dots {
+            *div = div.wrapping_add(1);
+            audio.tick((*div >> 8) as u8);
+        }
+    }
+
+    fn powered_audio() -> Audio {
+        let mut audio = Audio::new();
+        audio.write_register(NR52, 0x80);
+        audio.write_register(NR50, 0x77);
+        audio.write_register(NR51, 0xFF);
+        audio
+    }
+
+    #[test]
+    fn test_power_off_clears_registers() {
+        let mut audio = powered_audio();
+        audio.write_register(0xFF12, 0xF0);
+        audio.write_register(WAVE_RAM_BEGIN, 0x12);
+        assert_eq!(audio.read_register(0xFF12), 0xF0);
+        assert_eq!(audio.read_register(0xFF11), 0x3F);
+        assert_eq!(audio.read_register(0xFF27), 0xFF);
+
+        audio.write_register(NR52, 0x00);
+        assert_eq!(audio.read_register(0xFF12), 0x00);
+        assert_eq!(audio.read_register(NR50), 0x00);
+        assert_eq!(audio.read_register(NR52), 0x70);
+        assert_eq!(audio.read_register(WAVE_RAM_BEGIN), 0x12);
+
+        // Writes are ignored until the APU is powered again
+        audio.write_register(0xFF12, 0xF0);
+        assert_eq!(audio.read_register(0xFF12), 0x00);
+    }
+
+    #[test]
+    fn test_trigger_and_length() {
+        let mut audio = powered_audio();
+        let mut div = 0;
+        audio.write_register(0xFF17, 0xF0);
+        // 64 - 62 = 2 length ticks
+        audio.write_register(0xFF16, 62);
+        audio.write_register(0xFF19, 0xC0);
+        assert_eq!(audio.read_register(NR52) & 0x0F, 0b0010);
+
+        // Lengths are clocked at 256 Hz
+        run(&mut audio, &mut div, DOT_CYCLES_PER_SECOND / 256 * 3);
+        assert_eq!(audio.read_register(NR52) & 0x0F, 0b0000);
+    }
+
+    #[test]
+    fn test_dac_off_disables_channel() {
+        let mut audio = powered_audio();
+        audio.write_register(0xFF1A, 0x80);
+        audio.write_register(0xFF1E, 0x80);
+        assert_eq!(audio.read_register(NR52) & 0x0F, 0b0100);
+        audio.write_register(0xFF1A, 0x00);
+        assert_eq!(audio.read_register(NR52) & 0x0F, 0b0000);
+    }
+
+    #[test]
+    fn test_sweep_overflow_on_trigger() {
+        let mut audio = powered_audio();
+        audio.write_register(0xFF10, 0x11);
+        audio.write_register(0xFF12, 0xF0);
+        audio.write_register(0xFF13, 0xFF);
+        audio.write_register(0xFF14, 0x87);
+        assert_eq!(audio.read_register(NR52) & 0x01, 0);
+    }
+
+    #[test]
+    fn test_sample_rate() {
+        let mut audio = powered_audio();
+        audio.set_sample_rate(44_100);
+        let mut div = 0;
+        audio.write_register(0xFF17, 0xF0);
+        audio.write_register(0xFF18, 0x00);
+        audio.write_register(0xFF19, 0x87);
+
+        audio.set_capture_channels(true);
+        run(&mut audio, &mut div, DOT_CYCLES_PER_SECOND / 10);
+        assert!((4409..=4410).contains(&audio.samples().len()));
+        assert_eq!(audio.channel_samples().len(), audio.samples().len());
+        // Only pulse 2 is playing
+        assert!(audio.channel_samples().iter().any(|channels| channels[1] != 0.0));
+        assert!(audio.channel_samples().iter().all(|channels| channels[0] == 0.0));
+        let peak = audio
+            .samples()
+            .iter()
+            .map(|[left, right]| left.abs().max(right.abs()))
+            .fold(0.0, f32::max);
+        assert!(peak > 0.1 && peak <= 1.0);
+    }
+}
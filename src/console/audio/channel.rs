@@ -0,0 +1,145 @@
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+/// Silences a channel after a programmable time, clocked at 256 Hz by the frame sequencer
+pub struct LengthCounter {
+    // 64 for the pulse and noise channels, 256 for the wave channel
+    max: u16,
+    remaining: u16,
+    enabled: bool,
+}
+
+impl LengthCounter {
+    pub fn new(max: u16) -> Self {
+        Self {
+            max,
+            remaining: 0,
+            enabled: false,
+        }
+    }
+
+    /// NRx1 write, the register holds how many ticks are already used up
+    pub fn load(&mut self, length: u16) {
+        self.remaining = self.max - length;
+    }
+
+    /// Returns true once the counter expires and the channel has to be turned off
+    pub fn clock(&mut self) -> bool {
+        if !self.enabled || self.remaining == 0 {
+            return false;
+        }
+        self.remaining -= 1;
+        self.remaining == 0
+    }
+
+    /// NRx4 write of the length enable bit. Enabling it while the next frame sequencer step
+    /// does not clock lengths clocks it once right away, returns true if that expired it
+    pub fn set_enabled(&mut self, enabled: bool, next_step_clocks_length: bool) -> bool {
+        let was_enabled = self.enabled;
+        self.enabled = enabled;
+        !was_enabled && !next_step_clocks_length && self.clock()
+    }
+
+    /// A trigger reloads an expired counter, again with the extra clock when due
+    pub fn trigger(&mut self, next_step_clocks_length: bool) {
+        if self.remaining == 0 {
+            self.remaining = self.max;
+            if self.enabled && !next_step_clocks_length {
+                self.remaining -= 1;
+            }
+        }
+    }
+
+    /// Power off keeps the counter on the DMG, only the enable bit in NRx4 is cleared
+    pub fn power_off(&mut self) {
+        self.enabled = false;
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u16(self.remaining);
+        state.write_bool(self.enabled);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.remaining = state.read_u16()?;
+        self.enabled = state.read_bool()?;
+        Ok(())
+    }
+}
+
+/// Volume envelope of the pulse and noise channels, clocked at 64 Hz. Configured by NRx2:
+/// initial volume in the high nibble, direction in bit 3 and pace in bits 0-2
+#[derive(Default)]
+pub struct Envelope {
+    timer: u8,
+    volume: u8,
+}
+
+impl Envelope {
+    pub fn volume(&self) -> u8 {
+        self.volume
+    }
+
+    /// The DAC is powered as long as NRx2 does not mute the channel for good
+    pub fn dac_enabled(nrx2: u8) -> bool {
+        nrx2 & 0xF8 != 0
+    }
+
+    pub fn trigger(&mut self, nrx2: u8) {
+        self.timer = nrx2 & 0x07;
+        self.volume = nrx2 >> 4;
+    }
+
+    pub fn clock(&mut self, nrx2: u8) {
+        let pace = nrx2 & 0x07;
+        if pace == 0 {
+            return;
+        }
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = pace;
+            let increase = nrx2 & 0x08 != 0;
+            if increase && self.volume < 0x0F {
+                self.volume += 1;
+            } else if !increase && self.volume > 0 {
+                self.volume -= 1;
+            }
+        }
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.timer);
+        state.write_u8(self.volume);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.timer = state.read_u8()?;
+        self.volume = state.read_u8()?;
+        Ok(())
+    }
+}
+
+/// Output of a channel DAC, digital 0-15 maps linearly onto 1.0..=-1.0 and a disabled DAC
+/// outputs silence
+pub fn dac_output(dac_enabled: bool, digital: u8) -> f32 {
+    if !dac_enabled {
+        return 0.0;
+    }
+    1.0 - digital as f32 / 7.5
+}
+
+/// Frequency timer shared by all channels: counts down dot cycles and returns how many times
+/// it reloaded with `period` while running for `dots`
+pub fn run_timer(timer: &mut u32, dots: u32, period: u32) -> u32 {
+    let mut dots = dots;
+    let mut reloads = 0;
+    while dots >= *timer {
+        dots -= *timer;
+        *timer = period;
+        reloads += 1;
+    }
+    *timer -= dots;
+    reloads
+}
@@ -0,0 +1,5 @@
+pub mod apu;
+mod channel;
+mod noise;
+mod pulse;
+mod wave;
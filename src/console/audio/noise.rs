@@ -0,0 +1,135 @@
+use crate::console::audio::channel::{dac_output, run_timer, Envelope, LengthCounter};
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+// Dot cycles between LFSR steps for each NR43 divisor code, before the shift
+const DIVISORS: [u32; 8] = [8, 16, 32, 48, 64, 80, 96, 112];
+
+/// Channel 4, pseudo random noise from a 15 or 7 bit linear feedback shift register
+pub struct Noise {
+    // NR40-NR44 as written, NR40 does not exist
+    regs: [u8; 5],
+    enabled: bool,
+    timer: u32,
+    lfsr: u16,
+    pub length: LengthCounter,
+    envelope: Envelope,
+}
+
+impl Noise {
+    pub fn new() -> Self {
+        Self {
+            regs: [0; 5],
+            enabled: false,
+            timer: 0,
+            lfsr: 0,
+            length: LengthCounter::new(64),
+            envelope: Envelope::default(),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn dac_enabled(&self) -> bool {
+        Envelope::dac_enabled(self.regs[2])
+    }
+
+    fn period(&self) -> u32 {
+        let nr43 = self.regs[3];
+        DIVISORS[(nr43 & 0x07) as usize] << (nr43 >> 4)
+    }
+
+    pub fn read(&self, reg: usize) -> u8 {
+        self.regs[reg]
+    }
+
+    pub fn write(&mut self, reg: usize, value: u8, next_step_clocks_length: bool) {
+        self.regs[reg] = value;
+        match reg {
+            1 => self.length.load((value & 0x3F) as u16),
+            2 => {
+                if !self.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            4 => {
+                if self.length.set_enabled(value & 0x40 != 0, next_step_clocks_length) {
+                    self.enabled = false;
+                }
+                if value & 0x80 != 0 {
+                    self.enabled = self.dac_enabled();
+                    self.length.trigger(next_step_clocks_length);
+                    self.timer = self.period();
+                    self.envelope.trigger(self.regs[2]);
+                    self.lfsr = 0x7FFF;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock(self.regs[2]);
+    }
+
+    pub fn tick(&mut self, dots: u32) {
+        let period = self.period();
+        let steps = run_timer(&mut self.timer, dots, period);
+        // Shifts of 14 and 15 stop the LFSR
+        if self.regs[3] >> 4 >= 14 {
+            return;
+        }
+        for _ in 0..steps {
+            let feedback = (self.lfsr ^ (self.lfsr >> 1)) & 1;
+            self.lfsr = (self.lfsr >> 1) | (feedback << 14);
+            if self.regs[3] & 0x08 != 0 {
+                self.lfsr = (self.lfsr & !0x40) | (feedback << 6);
+            }
+        }
+    }
+
+    pub fn output(&self) -> f32 {
+        let digital = if self.enabled && self.lfsr & 1 == 0 {
+            self.envelope.volume()
+        } else {
+            0
+        };
+        dac_output(self.dac_enabled(), digital)
+    }
+
+    /// Clears every register, only the length counter survives on the DMG
+    pub fn power_off(&mut self) {
+        let mut length = core::mem::replace(&mut self.length, LengthCounter::new(64));
+        length.power_off();
+        *self = Self {
+            length,
+            ..Self::new()
+        };
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bytes(&self.regs);
+        state.write_bool(self.enabled);
+        state.write_u32(self.timer);
+        state.write_u16(self.lfsr);
+        self.length.save_state(state);
+        self.envelope.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        state.read_into(&mut self.regs)?;
+        self.enabled = state.read_bool()?;
+        self.timer = state.read_u32()?;
+        self.lfsr = state.read_u16()?;
+        self.length.load_state(state)?;
+        self.envelope.load_state(state)
+    }
+}
@@ -0,0 +1,214 @@
+use crate::console::audio::channel::{dac_output, run_timer, Envelope, LengthCounter};
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+// Waveforms for 12.5%, 25%, 50% and 75% duty, played from bit 0 up
+const DUTY_PATTERNS: [u8; 4] = [0b1000_0000, 0b1000_0001, 0b1110_0001, 0b0111_1110];
+const MAX_FREQUENCY: u16 = 0x7FF;
+
+/// Square wave channel 1 (with frequency sweep) or 2 (without)
+pub struct Pulse {
+    has_sweep: bool,
+    // NRx0-NRx4 as written, NR20 does not exist
+    regs: [u8; 5],
+    enabled: bool,
+    timer: u32,
+    duty_position: u8,
+    pub length: LengthCounter,
+    envelope: Envelope,
+    sweep_timer: u8,
+    sweep_enabled: bool,
+    shadow_frequency: u16,
+    // Set once a sweep calculation subtracted, clearing the direction afterwards kills the channel
+    sweep_negated: bool,
+}
+
+impl Pulse {
+    pub fn new(has_sweep: bool) -> Self {
+        Self {
+            has_sweep,
+            regs: [0; 5],
+            enabled: false,
+            timer: 0,
+            duty_position: 0,
+            length: LengthCounter::new(64),
+            envelope: Envelope::default(),
+            sweep_timer: 0,
+            sweep_enabled: false,
+            shadow_frequency: 0,
+            sweep_negated: false,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn frequency(&self) -> u16 {
+        (((self.regs[4] & 0x07) as u16) << 8) | self.regs[3] as u16
+    }
+
+    fn set_frequency(&mut self, frequency: u16) {
+        self.regs[3] = frequency as u8;
+        self.regs[4] = (self.regs[4] & !0x07) | ((frequency >> 8) as u8 & 0x07);
+    }
+
+    fn period(&self) -> u32 {
+        (2048 - self.frequency() as u32) * 4
+    }
+
+    fn dac_enabled(&self) -> bool {
+        Envelope::dac_enabled(self.regs[2])
+    }
+
+    pub fn read(&self, reg: usize) -> u8 {
+        self.regs[reg]
+    }
+
+    pub fn write(&mut self, reg: usize, value: u8, next_step_clocks_length: bool) {
+        self.regs[reg] = value;
+        match reg {
+            0 => {
+                if self.sweep_negated && value & 0x08 == 0 {
+                    self.enabled = false;
+                }
+            }
+            1 => self.length.load((value & 0x3F) as u16),
+            2 => {
+                if !self.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            4 => {
+                if self.length.set_enabled(value & 0x40 != 0, next_step_clocks_length) {
+                    self.enabled = false;
+                }
+                if value & 0x80 != 0 {
+                    self.trigger(next_step_clocks_length);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn trigger(&mut self, next_step_clocks_length: bool) {
+        self.enabled = self.dac_enabled();
+        self.length.trigger(next_step_clocks_length);
+        self.timer = self.period();
+        self.envelope.trigger(self.regs[2]);
+
+        if self.has_sweep {
+            let pace = (self.regs[0] >> 4) & 0x07;
+            let shift = self.regs[0] & 0x07;
+            self.shadow_frequency = self.frequency();
+            self.sweep_timer = if pace == 0 { 8 } else { pace };
+            self.sweep_enabled = pace != 0 || shift != 0;
+            self.sweep_negated = false;
+            if shift != 0 {
+                self.sweep_frequency();
+            }
+        }
+    }
+
+    /// Next frequency of the sweep, disables the channel when it overflows
+    fn sweep_frequency(&mut self) -> u16 {
+        let delta = self.shadow_frequency >> (self.regs[0] & 0x07);
+        let frequency = if self.regs[0] & 0x08 != 0 {
+            self.sweep_negated = true;
+            self.shadow_frequency - delta
+        } else {
+            self.shadow_frequency + delta
+        };
+        if frequency > MAX_FREQUENCY {
+            self.enabled = false;
+        }
+        frequency
+    }
+
+    pub fn clock_sweep(&mut self) {
+        if self.sweep_timer > 0 {
+            self.sweep_timer -= 1;
+        }
+        if self.sweep_timer != 0 {
+            return;
+        }
+
+        let pace = (self.regs[0] >> 4) & 0x07;
+        self.sweep_timer = if pace == 0 { 8 } else { pace };
+        if !self.sweep_enabled || pace == 0 {
+            return;
+        }
+
+        let frequency = self.sweep_frequency();
+        if frequency <= MAX_FREQUENCY && self.regs[0] & 0x07 != 0 {
+            self.shadow_frequency = frequency;
+            self.set_frequency(frequency);
+            // The new frequency is checked for overflow right away, but not applied
+            self.sweep_frequency();
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn clock_envelope(&mut self) {
+        self.envelope.clock(self.regs[2]);
+    }
+
+    pub fn tick(&mut self, dots: u32) {
+        let period = self.period();
+        let steps = run_timer(&mut self.timer, dots, period);
+        self.duty_position = ((self.duty_position as u32 + steps) % 8) as u8;
+    }
+
+    pub fn output(&self) -> f32 {
+        let duty = DUTY_PATTERNS[(self.regs[1] >> 6) as usize];
+        let high = (duty >> self.duty_position) & 1 != 0;
+        let digital = if self.enabled && high {
+            self.envelope.volume()
+        } else {
+            0
+        };
+        dac_output(self.dac_enabled(), digital)
+    }
+
+    /// Clears every register, only the length counter survives on the DMG
+    pub fn power_off(&mut self) {
+        let mut length = core::mem::replace(&mut self.length, LengthCounter::new(64));
+        length.power_off();
+        *self = Self {
+            length,
+            ..Self::new(self.has_sweep)
+        };
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bytes(&self.regs);
+        state.write_bool(self.enabled);
+        state.write_u32(self.timer);
+        state.write_u8(self.duty_position);
+        self.length.save_state(state);
+        self.envelope.save_state(state);
+        state.write_u8(self.sweep_timer);
+        state.write_bool(self.sweep_enabled);
+        state.write_u16(self.shadow_frequency);
+        state.write_bool(self.sweep_negated);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        state.read_into(&mut self.regs)?;
+        self.enabled = state.read_bool()?;
+        self.timer = state.read_u32()?;
+        self.duty_position = state.read_u8()? % 8;
+        self.length.load_state(state)?;
+        self.envelope.load_state(state)?;
+        self.sweep_timer = state.read_u8()?;
+        self.sweep_enabled = state.read_bool()?;
+        self.shadow_frequency = state.read_u16()?;
+        self.sweep_negated = state.read_bool()?;
+        Ok(())
+    }
+}
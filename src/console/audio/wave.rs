@@ -0,0 +1,134 @@
+use crate::console::audio::channel::{dac_output, run_timer, LengthCounter};
+use crate::console::constants::WAVE_RAM_SIZE;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+/// Channel 3, plays the 32 4-bit samples of wave RAM
+pub struct Wave {
+    // NR30-NR34 as written
+    regs: [u8; 5],
+    pub ram: [u8; WAVE_RAM_SIZE],
+    enabled: bool,
+    timer: u32,
+    position: u8,
+    sample: u8,
+    pub length: LengthCounter,
+}
+
+impl Wave {
+    pub fn new() -> Self {
+        Self {
+            regs: [0; 5],
+            ram: [0; WAVE_RAM_SIZE],
+            enabled: false,
+            timer: 0,
+            position: 0,
+            sample: 0,
+            length: LengthCounter::new(256),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn dac_enabled(&self) -> bool {
+        self.regs[0] & 0x80 != 0
+    }
+
+    fn period(&self) -> u32 {
+        let frequency = (((self.regs[4] & 0x07) as u32) << 8) | self.regs[3] as u32;
+        (2048 - frequency) * 2
+    }
+
+    pub fn read(&self, reg: usize) -> u8 {
+        self.regs[reg]
+    }
+
+    pub fn write(&mut self, reg: usize, value: u8, next_step_clocks_length: bool) {
+        self.regs[reg] = value;
+        match reg {
+            0 => {
+                if !self.dac_enabled() {
+                    self.enabled = false;
+                }
+            }
+            1 => self.length.load(value as u16),
+            4 => {
+                if self.length.set_enabled(value & 0x40 != 0, next_step_clocks_length) {
+                    self.enabled = false;
+                }
+                if value & 0x80 != 0 {
+                    self.enabled = self.dac_enabled();
+                    self.length.trigger(next_step_clocks_length);
+                    self.timer = self.period();
+                    self.position = 0;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clock_length(&mut self) {
+        if self.length.clock() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn tick(&mut self, dots: u32) {
+        let period = self.period();
+        let steps = run_timer(&mut self.timer, dots, period);
+        if steps == 0 || !self.enabled {
+            return;
+        }
+        self.position = ((self.position as u32 + steps) % 32) as u8;
+        let byte = self.ram[self.position as usize / 2];
+        // High nibble first
+        self.sample = if self.position.is_multiple_of(2) {
+            byte >> 4
+        } else {
+            byte & 0x0F
+        };
+    }
+
+    pub fn output(&self) -> f32 {
+        // NR32 bits 5-6: mute, 100%, 50%, 25%
+        let digital = match (self.regs[2] >> 5) & 0x03 {
+            0 => 0,
+            volume => self.sample >> (volume - 1),
+        };
+        let digital = if self.enabled { digital } else { 0 };
+        dac_output(self.dac_enabled(), digital)
+    }
+
+    /// Clears every register, the length counter and wave RAM survive on the DMG
+    pub fn power_off(&mut self) {
+        let mut length = core::mem::replace(&mut self.length, LengthCounter::new(256));
+        length.power_off();
+        *self = Self {
+            length,
+            ram: self.ram,
+            ..Self::new()
+        };
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bytes(&self.regs);
+        state.write_bytes(&self.ram);
+        state.write_bool(self.enabled);
+        state.write_u32(self.timer);
+        state.write_u8(self.position);
+        state.write_u8(self.sample);
+        self.length.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        state.read_into(&mut self.regs)?;
+        state.read_into(&mut self.ram)?;
+        self.enabled = state.read_bool()?;
+        self.timer = state.read_u32()?;
+        self.position = state.read_u8()? % 32;
+        self.sample = state.read_u8()? & 0x0F;
+        self.length.load_state(state)
+    }
+}
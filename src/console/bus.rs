@@ -1,10 +1,19 @@
-use crate::console::audio::Audio;
-use crate::console::cartridge::Cartridge;
+use crate::console::audio::apu::Audio;
+use crate::console::cartridge::cartridge::{Cartridge, RumbleCallback};
+use crate::console::cartridge::header::CartridgeHeader;
+use crate::console::cartridge::load_error::LoadError;
 use crate::console::constants::*;
+use crate::console::debugger::{WatchHit, Watchpoint};
 use crate::console::gui::gpu::{Gpu, PixelLevel};
 use crate::console::hw_register::HwRegister;
 use crate::console::hw_register::HwRegisters;
 use crate::console::interrupt::Interrupt;
+use crate::console::save_state::{StateReader, StateWriter};
+use crate::console::serial::{Serial, SerialDevice};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::Cell;
 
 pub struct Bus {
     ram: [u8; MEMORY_SIZE as usize],
@@ -13,7 +22,13 @@ pub struct Bus {
     cartridge: Cartridge,
     gpu: Gpu,
     audio: Audio,
+    serial: Serial,
     hw_registers: HwRegisters,
+    gpu_enabled: bool,
+    watchpoints: Vec<Watchpoint>,
+    // Only accesses made by the CPU are watched
+    watching: bool,
+    watch_hit: Cell<Option<WatchHit>>,
 }
 
 impl Bus {
@@ -32,6 +47,12 @@ impl Bus {
             EXT_RAM_BEGIN..=EXT_RAM_END => {
                 self.cartridge.write_ram(addr, value);
             }
+            AUDIO_REGS_BEGIN..=AUDIO_REGS_END => {
+                self.audio.write_register(addr, value);
+            }
+            SERIAL_SB_ADDR..=SERIAL_SC_ADDR => {
+                self.serial.write_register(addr, value);
+            }
             addr if HwRegister::supported_addr(addr) => {
                 self.hw_registers.write_to_register_addr(addr, value)
             }
@@ -48,6 +69,8 @@ impl Bus {
             ROM_BANK_0_BEGIN..=ROM_BANK_N_END => self.cartridge.read_rom(addr),
             VRAM_BEGIN..=VRAM_END => self.gpu.read_from_vram(addr - VRAM_BEGIN),
             EXT_RAM_BEGIN..=EXT_RAM_END => self.cartridge.read_ram(addr),
+            AUDIO_REGS_BEGIN..=AUDIO_REGS_END => self.audio.read_register(addr),
+            SERIAL_SB_ADDR..=SERIAL_SC_ADDR => self.serial.read_register(addr),
             addr if HwRegister::supported_addr(addr) => {
                 self.hw_registers.read_from_register_addr(addr)
             }
@@ -58,31 +81,106 @@ impl Bus {
     //TODO: Maybe perform checks on who and when is attempting to access the bus and prevent it
     //      if it shouldn't be accessing it during this period
     pub fn write_to_8b(&mut self, addr: u16, value: u8) {
+        self.watch(true, addr, value);
         self.write_to_bus(addr, value);
     }
 
     pub fn read_from_8b(&self, addr: u16) -> u8 {
-        self.read_from_bus(addr)
+        let value = self.read_from_bus(addr);
+        self.watch(false, addr, value);
+        value
     }
 
     pub fn write_to_16b(&mut self, addr: u16, value: u16) {
         let bytes = value.to_le_bytes();
-        self.write_to_bus(addr, bytes[0]);
-        self.write_to_bus(addr.wrapping_add(1), bytes[1]);
+        self.write_to_8b(addr, bytes[0]);
+        self.write_to_8b(addr.wrapping_add(1), bytes[1]);
     }
 
     pub fn read_from_16b(&self, addr: u16) -> u16 {
         let bytes = [
-            self.read_from_bus(addr),
-            self.read_from_bus(addr.wrapping_add(1)),
+            self.read_from_8b(addr),
+            self.read_from_8b(addr.wrapping_add(1)),
         ];
         u16::from_le_bytes(bytes)
     }
 
-    pub fn load_rom(&mut self, data: &[u8; CARTRIDGE_SIZE]) {
-        self.cartridge.load_rom(data);
+    /// Reads without tripping watchpoints, for opcode fetches and the debugger itself
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.read_from_bus(addr)
+    }
 
+    #[inline(always)]
+    fn watch(&self, write: bool, addr: u16, value: u8) {
+        if !self.watching || self.watch_hit.get().is_some() {
+            return;
+        }
+        if self.watchpoints.iter().any(|w| w.matches(write, addr)) {
+            self.watch_hit.set(Some(WatchHit { write, addr, value }));
+        }
+    }
+
+    pub fn set_watchpoints(&mut self, watchpoints: &[Watchpoint]) {
+        self.watchpoints = watchpoints.to_vec();
+    }
+
+    /// Watches the accesses until called again with `false`, a no-op without watchpoints
+    pub fn set_watching(&mut self, watching: bool) {
+        self.watching = watching && !self.watchpoints.is_empty();
+    }
+
+    pub fn take_watch_hit(&mut self) -> Option<WatchHit> {
+        self.watch_hit.take()
+    }
+
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<&CartridgeHeader, LoadError> {
         self.gpu.vram.fill(0);
+
+        self.cartridge.load_rom(data)
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bytes(&self.ram);
+        state.write_bool(self.boot_rom_enabled);
+        self.hw_registers.save_state(state);
+        self.gpu.save_state(state);
+        self.audio.save_state(state);
+        self.serial.save_state(state);
+        self.cartridge.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        state.read_into(&mut self.ram)?;
+        self.boot_rom_enabled = state.read_bool()?;
+        self.hw_registers.load_state(state)?;
+        self.gpu.load_state(state)?;
+        self.audio.load_state(state)?;
+        self.serial.load_state(state)?;
+        self.cartridge.load_state(state)
+    }
+
+    pub fn audio(&self) -> &Audio {
+        &self.audio
+    }
+
+    pub fn audio_mut(&mut self) -> &mut Audio {
+        &mut self.audio
+    }
+
+    pub fn cartridge(&self) -> &Cartridge {
+        &self.cartridge
+    }
+
+    pub fn cartridge_mut(&mut self) -> &mut Cartridge {
+        &mut self.cartridge
+    }
+
+    pub fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.cartridge.set_rumble_callback(callback);
+    }
+
+    pub fn set_serial_device(&mut self, device: Box<dyn SerialDevice>) {
+        self.serial.set_device(device);
     }
 
     pub fn get_interrupt(&self) -> Option<(Interrupt, u16)> {
@@ -108,13 +206,23 @@ impl Bus {
             ram: [0u8; MEMORY_SIZE as usize],
             gpu: Gpu::new(),
             audio: Audio::new(),
+            serial: Serial::new(),
             cartridge: Cartridge::new(),
             hw_registers: HwRegisters::default(),
             boot_rom: BOOT_ROM,
             boot_rom_enabled: true,
+            gpu_enabled: true,
+            watchpoints: Vec::new(),
+            watching: false,
+            watch_hit: Cell::new(None),
         }
     }
 
+    /// Leaves the PPU out of `tick`, nothing is drawn and it raises no interrupts
+    pub fn set_gpu_enabled(&mut self, gpu_enabled: bool) {
+        self.gpu_enabled = gpu_enabled;
+    }
+
     pub fn get_gpu_buffer(&self) -> &[PixelLevel; SCREEN_WIDTH * SCREEN_HEIGHT] {
         &self.gpu.buffer
     }
@@ -148,6 +256,19 @@ impl Bus {
             }
         }
 
+        self.cartridge.tick();
+
+        self.audio
+            .tick(self.hw_registers.read_from_register(HwRegister::DIV));
+
+        if self.serial.tick() {
+            self.hw_registers.request_interrupt(Interrupt::Serial);
+        }
+
+        if !self.gpu_enabled {
+            return;
+        }
+
         self.hw_registers.update_stat_line();
 
         self.gpu.tick(&mut self.hw_registers, &self.ram[OAM_BEGIN as usize..=OAM_END as usize].try_into().unwrap());
@@ -0,0 +1,170 @@
+use crate::console::cartridge::header::CartridgeHeader;
+use crate::console::cartridge::load_error::LoadError;
+use crate::console::cartridge::mapper::mapper::{mapper_from_header, Mapper};
+use crate::console::cartridge::mapper::rom_only::RomOnly;
+use crate::console::constants::*;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// Called with the new motor state whenever a rumble cartridge turns its motor on or off
+pub type RumbleCallback = Box<dyn FnMut(bool)>;
+
+pub struct Cartridge {
+    rom: Vec<u8>,
+    ram: Vec<u8>,
+    header: Option<CartridgeHeader>,
+    mapper: Box<dyn Mapper>,
+    rumble_callback: Option<RumbleCallback>,
+    has_battery: bool,
+    ram_dirty: bool,
+    save_pending: bool,
+}
+
+// Every mapper has its RAM enable register in this range
+const RAM_ENABLE_END: u16 = 0x1FFF;
+const RAM_ENABLE_VALUE: u8 = 0x0A;
+
+impl Cartridge {
+    pub fn new() -> Self {
+        Self {
+            rom: Vec::new(),
+            ram: Vec::new(),
+            header: None,
+            mapper: Box::new(RomOnly::new()),
+            rumble_callback: None,
+            has_battery: false,
+            ram_dirty: false,
+            save_pending: false,
+        }
+    }
+
+    /// Refuses ROMs without a complete header or with a banking chip that is not emulated
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<&CartridgeHeader, LoadError> {
+        let header =
+            CartridgeHeader::parse(data).ok_or(LoadError::TruncatedHeader { len: data.len() })?;
+
+        // Dumps shorter than what the header declares are padded with open bus values
+        let mut rom = vec![0xFF; header.rom_size().unwrap_or(0).max(data.len())];
+        rom[..data.len()].copy_from_slice(data);
+
+        let mapper = mapper_from_header(&header, &rom)
+            .ok_or(LoadError::UnsupportedMapper(header.cartridge_type))?;
+
+        let ram_size = match mapper.builtin_ram_size() {
+            Some(builtin_ram_size) => builtin_ram_size,
+            None => header.ram_size().unwrap_or(MAX_RAM_SIZE),
+        };
+        self.rom = rom;
+        self.mapper = mapper;
+        self.ram = vec![0u8; ram_size];
+        self.ram_dirty = false;
+        self.save_pending = false;
+        self.has_battery = header.cartridge_type.has_battery;
+
+        Ok(self.header.insert(header))
+    }
+
+    pub fn has_battery(&self) -> bool {
+        self.has_battery
+    }
+
+    /// True when the RAM was written to since the last battery save
+    pub fn is_ram_dirty(&self) -> bool {
+        self.ram_dirty
+    }
+
+    /// True once after the game disabled RAM following a write, which is when games are done
+    /// saving and the RAM is consistent
+    pub fn take_save_pending(&mut self) -> bool {
+        core::mem::take(&mut self.save_pending)
+    }
+
+    /// Raw RAM followed by the mapper footer, the `.sav` layout used by other emulators. The RAM
+    /// stays dirty until `mark_saved`
+    pub fn save_battery(&self, timestamp: u64) -> Vec<u8> {
+        let mut data = self.ram.clone();
+        if let Some(footer) = self.mapper.battery_footer(timestamp) {
+            data.extend_from_slice(&footer);
+        }
+        data
+    }
+
+    /// Called once the data from `save_battery` was written
+    pub fn mark_saved(&mut self) {
+        self.ram_dirty = false;
+        self.save_pending = false;
+    }
+
+    pub fn load_battery(&mut self, data: &[u8], timestamp: u64) {
+        let ram_len = self.ram.len().min(data.len());
+        self.ram[..ram_len].copy_from_slice(&data[..ram_len]);
+        if data.len() > self.ram.len() {
+            self.mapper.load_battery_footer(&data[self.ram.len()..], timestamp);
+        }
+    }
+
+    pub fn header(&self) -> Option<&CartridgeHeader> {
+        self.header.as_ref()
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_vec(&self.ram);
+        self.mapper.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        state.read_vec_into(&mut self.ram)?;
+        self.mapper.load_state(state)?;
+        // The restored RAM differs from the battery save
+        self.ram_dirty = true;
+        Ok(())
+    }
+
+    pub fn set_rumble_callback(&mut self, callback: RumbleCallback) {
+        self.rumble_callback = Some(callback);
+    }
+
+    pub fn tick(&mut self) {
+        self.mapper.tick();
+    }
+
+    pub fn read_rom(&self, addr: u16) -> u8 {
+        if self.rom.is_empty() { return 0xFF; }
+        self.mapper.read_rom(&self.rom, addr)
+    }
+
+    /// ROM bank currently mapped at 0x4000-0x7FFF
+    pub fn rom_bank(&self) -> usize {
+        let bank_count = (self.rom.len() / ROM_BANK_SIZE).max(1);
+        self.mapper.rom_bank() % bank_count
+    }
+
+    pub fn write_rom(&mut self, addr: u16, value: u8) {
+        if self.ram_dirty && addr <= RAM_ENABLE_END && (value & 0x0F) != RAM_ENABLE_VALUE {
+            self.save_pending = true;
+        }
+
+        let motor_was_on = self.mapper.motor_on();
+        self.mapper.write_rom(addr, value);
+
+        let motor_on = self.mapper.motor_on();
+        if motor_on != motor_was_on
+            && let Some(callback) = self.rumble_callback.as_mut()
+        {
+            callback(motor_on);
+        }
+    }
+
+    pub fn read_ram(&self, addr: u16) -> u8 {
+        self.mapper.read_ram(&self.ram, addr)
+    }
+
+    pub fn write_ram(&mut self, addr: u16, value: u8) {
+        if self.mapper.write_ram(&mut self.ram, addr, value) {
+            self.ram_dirty = true;
+        }
+    }
+}
@@ -0,0 +1,370 @@
+use crate::console::constants::*;
+use alloc::string::String;
+use core::fmt;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MapperKind {
+    RomOnly,
+    Mbc1,
+    Mbc2,
+    Mmm01,
+    Mbc3,
+    Mbc5,
+    Mbc6,
+    Mbc7,
+    PocketCamera,
+    Tama5,
+    Huc3,
+    Huc1,
+}
+
+impl MapperKind {
+    pub fn is_supported(&self) -> bool {
+        use MapperKind::*;
+        matches!(self, RomOnly | Mbc1 | Mbc2 | Mbc3 | Mbc5 | Huc1)
+    }
+}
+
+impl fmt::Display for MapperKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MapperKind::*;
+        match self {
+            RomOnly => write!(f, "ROM ONLY"),
+            Mbc1 => write!(f, "MBC1"),
+            Mbc2 => write!(f, "MBC2"),
+            Mmm01 => write!(f, "MMM01"),
+            Mbc3 => write!(f, "MBC3"),
+            Mbc5 => write!(f, "MBC5"),
+            Mbc6 => write!(f, "MBC6"),
+            Mbc7 => write!(f, "MBC7"),
+            PocketCamera => write!(f, "POCKET CAMERA"),
+            Tama5 => write!(f, "BANDAI TAMA5"),
+            Huc3 => write!(f, "HuC3"),
+            Huc1 => write!(f, "HuC1"),
+        }
+    }
+}
+
+/// Decoded cartridge type byte at 0x0147
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CartridgeType {
+    pub code: u8,
+    /// `None` for codes that are not assigned to any cartridge
+    pub mapper: Option<MapperKind>,
+    pub has_ram: bool,
+    pub has_battery: bool,
+    pub has_timer: bool,
+    pub has_rumble: bool,
+}
+
+impl CartridgeType {
+    pub fn from_code(code: u8) -> Self {
+        use MapperKind::*;
+        // (mapper, ram, battery, timer, rumble)
+        let (mapper, has_ram, has_battery, has_timer, has_rumble) = match code {
+            0x00 => (Some(RomOnly), false, false, false, false),
+            0x01 => (Some(Mbc1), false, false, false, false),
+            0x02 => (Some(Mbc1), true, false, false, false),
+            0x03 => (Some(Mbc1), true, true, false, false),
+            0x05 => (Some(Mbc2), false, false, false, false),
+            0x06 => (Some(Mbc2), false, true, false, false),
+            0x08 => (Some(RomOnly), true, false, false, false),
+            0x09 => (Some(RomOnly), true, true, false, false),
+            0x0B => (Some(Mmm01), false, false, false, false),
+            0x0C => (Some(Mmm01), true, false, false, false),
+            0x0D => (Some(Mmm01), true, true, false, false),
+            0x0F => (Some(Mbc3), false, true, true, false),
+            0x10 => (Some(Mbc3), true, true, true, false),
+            0x11 => (Some(Mbc3), false, false, false, false),
+            0x12 => (Some(Mbc3), true, false, false, false),
+            0x13 => (Some(Mbc3), true, true, false, false),
+            0x19 => (Some(Mbc5), false, false, false, false),
+            0x1A => (Some(Mbc5), true, false, false, false),
+            0x1B => (Some(Mbc5), true, true, false, false),
+            0x1C => (Some(Mbc5), false, false, false, true),
+            0x1D => (Some(Mbc5), true, false, false, true),
+            0x1E => (Some(Mbc5), true, true, false, true),
+            0x20 => (Some(Mbc6), false, false, false, false),
+            0x22 => (Some(Mbc7), true, true, false, true),
+            0xFC => (Some(PocketCamera), false, false, false, false),
+            0xFD => (Some(Tama5), false, false, false, false),
+            0xFE => (Some(Huc3), false, false, false, false),
+            0xFF => (Some(Huc1), true, true, false, false),
+            _ => (None, false, false, false, false),
+        };
+
+        Self {
+            code,
+            mapper,
+            has_ram,
+            has_battery,
+            has_timer,
+            has_rumble,
+        }
+    }
+}
+
+impl fmt::Display for CartridgeType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.mapper {
+            Some(mapper) => write!(f, "{}", mapper)?,
+            None => write!(f, "UNKNOWN")?,
+        }
+        if self.has_timer {
+            write!(f, "+TIMER")?;
+        }
+        if self.has_rumble {
+            write!(f, "+RUMBLE")?;
+        }
+        if self.has_ram {
+            write!(f, "+RAM")?;
+        }
+        if self.has_battery {
+            write!(f, "+BATTERY")?;
+        }
+        write!(f, " (${:02X})", self.code)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CgbSupport {
+    DmgOnly,
+    Enhanced,
+    CgbOnly,
+}
+
+/// Cartridge header at 0x0100-0x014F
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CartridgeHeader {
+    pub logo_valid: bool,
+    pub title: String,
+    /// Four letter code found on later CGB cartridges
+    pub manufacturer_code: Option<String>,
+    pub cgb_support: CgbSupport,
+    pub new_licensee_code: String,
+    pub sgb_support: bool,
+    pub cartridge_type: CartridgeType,
+    pub rom_size_code: u8,
+    pub ram_size_code: u8,
+    pub japanese: bool,
+    pub old_licensee_code: u8,
+    pub version: u8,
+    pub header_checksum: u8,
+    pub computed_header_checksum: u8,
+    pub global_checksum: u16,
+    pub computed_global_checksum: u16,
+}
+
+// Printable ASCII, anything else is dropped from the title
+pub(crate) fn header_string(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .take_while(|&&byte| byte != 0)
+        .filter(|byte| byte.is_ascii_graphic() || **byte == b' ')
+        .map(|&byte| byte as char)
+        .collect::<String>()
+        .trim_end()
+        .into()
+}
+
+impl CartridgeHeader {
+    /// Returns `None` if the ROM is too short to hold a header
+    pub fn parse(rom: &[u8]) -> Option<Self> {
+        if rom.len() < HEADER_END {
+            return None;
+        }
+
+        let cgb_flag = rom[CGB_FLAG_ADDR];
+        let cgb_support = match cgb_flag {
+            0xC0 => CgbSupport::CgbOnly,
+            flag if flag & 0x80 != 0 => CgbSupport::Enhanced,
+            _ => CgbSupport::DmgOnly,
+        };
+
+        // Older cartridges use the manufacturer and CGB bytes as part of a 16 character title
+        let manufacturer = &rom[MANUFACTURER_CODE_ADDR..CGB_FLAG_ADDR];
+        let has_manufacturer_code = cgb_support != CgbSupport::DmgOnly
+            && manufacturer
+                .iter()
+                .all(|byte| byte.is_ascii_uppercase() || byte.is_ascii_digit());
+        let title_end = match (has_manufacturer_code, cgb_support) {
+            (true, _) => MANUFACTURER_CODE_ADDR,
+            (false, CgbSupport::DmgOnly) => CGB_FLAG_ADDR + 1,
+            (false, _) => CGB_FLAG_ADDR,
+        };
+
+        let computed_header_checksum = rom[TITLE_ADDR..HEADER_CHECKSUM_ADDR]
+            .iter()
+            .fold(0u8, |checksum, &byte| {
+                checksum.wrapping_sub(byte).wrapping_sub(1)
+            });
+
+        let computed_global_checksum = rom
+            .iter()
+            .enumerate()
+            .filter(|(addr, _)| !(GLOBAL_CHECKSUM_ADDR..GLOBAL_CHECKSUM_ADDR + 2).contains(addr))
+            .fold(0u16, |checksum, (_, &byte)| {
+                checksum.wrapping_add(byte as u16)
+            });
+
+        Some(Self {
+            logo_valid: rom[NINTENDO_LOGO_ADDR..NINTENDO_LOGO_ADDR + NINTENDO_LOGO.len()]
+                == NINTENDO_LOGO,
+            title: header_string(&rom[TITLE_ADDR..title_end]),
+            manufacturer_code: has_manufacturer_code.then(|| header_string(manufacturer)),
+            cgb_support,
+            new_licensee_code: header_string(&rom[NEW_LICENSEE_CODE_ADDR..SGB_FLAG_ADDR]),
+            sgb_support: rom[SGB_FLAG_ADDR] == 0x03,
+            cartridge_type: CartridgeType::from_code(rom[CARTRIDGE_TYPE_ADDR]),
+            rom_size_code: rom[ROM_SIZE_ADDR],
+            ram_size_code: rom[RAM_SIZE_ADDR],
+            japanese: rom[DESTINATION_CODE_ADDR] == 0x00,
+            old_licensee_code: rom[OLD_LICENSEE_CODE_ADDR],
+            version: rom[VERSION_ADDR],
+            header_checksum: rom[HEADER_CHECKSUM_ADDR],
+            computed_header_checksum,
+            global_checksum: u16::from_be_bytes([
+                rom[GLOBAL_CHECKSUM_ADDR],
+                rom[GLOBAL_CHECKSUM_ADDR + 1],
+            ]),
+            computed_global_checksum,
+        })
+    }
+
+    pub fn rom_size(&self) -> Option<usize> {
+        match self.rom_size_code {
+            0x00..=0x08 => Some(MIN_ROM_SIZE << self.rom_size_code),
+            0x52 => Some(72 * ROM_BANK_SIZE),
+            0x53 => Some(80 * ROM_BANK_SIZE),
+            0x54 => Some(96 * ROM_BANK_SIZE),
+            _ => None,
+        }
+    }
+
+    pub fn ram_size(&self) -> Option<usize> {
+        match self.ram_size_code {
+            0x00 => Some(0),
+            0x01 => Some(2048),
+            0x02 => Some(8192),
+            0x03 => Some(32768),
+            0x04 => Some(131072),
+            0x05 => Some(65536),
+            _ => None,
+        }
+    }
+
+    /// The boot ROM refuses to start a cartridge with a bad header checksum
+    pub fn header_checksum_valid(&self) -> bool {
+        self.header_checksum == self.computed_header_checksum
+    }
+
+    /// Not verified by the hardware, a mismatch usually means a bad dump or a patched ROM
+    pub fn global_checksum_valid(&self) -> bool {
+        self.global_checksum == self.computed_global_checksum
+    }
+
+    pub fn licensee_code(&self) -> String {
+        if self.old_licensee_code == USE_NEW_LICENSEE_CODE {
+            self.new_licensee_code.clone()
+        } else {
+            alloc::format!("{:02X}", self.old_licensee_code)
+        }
+    }
+}
+
+impl fmt::Display for CartridgeHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let validity = |valid: bool| if valid { "ok" } else { "MISMATCH" };
+
+        writeln!(f, "Title:           {}", self.title)?;
+        if let Some(manufacturer_code) = &self.manufacturer_code {
+            writeln!(f, "Manufacturer:    {}", manufacturer_code)?;
+        }
+        writeln!(f, "Licensee:        {}", self.licensee_code())?;
+        writeln!(f, "Cartridge type:  {}", self.cartridge_type)?;
+        writeln!(
+            f,
+            "Emulated:        {}",
+            self.cartridge_type
+                .mapper
+                .is_some_and(|mapper| mapper.is_supported())
+        )?;
+        match self.rom_size() {
+            Some(size) => writeln!(f, "ROM size:        {} KiB", size / 1024)?,
+            None => writeln!(f, "ROM size:        unknown (${:02X})", self.rom_size_code)?,
+        }
+        match self.ram_size() {
+            Some(size) => writeln!(f, "RAM size:        {} KiB", size / 1024)?,
+            None => writeln!(f, "RAM size:        unknown (${:02X})", self.ram_size_code)?,
+        }
+        writeln!(f, "CGB support:     {:?}", self.cgb_support)?;
+        writeln!(f, "SGB support:     {}", self.sgb_support)?;
+        writeln!(
+            f,
+            "Destination:     {}",
+            if self.japanese { "Japan" } else { "Overseas" }
+        )?;
+        writeln!(f, "Version:         {}", self.version)?;
+        writeln!(f, "Nintendo logo:   {}", validity(self.logo_valid))?;
+        writeln!(
+            f,
+            "Header checksum: ${:02X} ({})",
+            self.header_checksum,
+            validity(self.header_checksum_valid())
+        )?;
+        write!(
+            f,
+            "Global checksum: ${:04X} ({})",
+            self.global_checksum,
+            validity(self.global_checksum_valid())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::cartridge::header::*;
+
+    fn test_rom() -> Vec<u8> {
+        let mut rom = vec![0u8; MIN_ROM_SIZE];
+        rom[NINTENDO_LOGO_ADDR..NINTENDO_LOGO_ADDR + NINTENDO_LOGO.len()]
+            .copy_from_slice(&NINTENDO_LOGO);
+        rom[TITLE_ADDR..TITLE_ADDR + 6].copy_from_slice(b"RUSTEM");
+        rom[CARTRIDGE_TYPE_ADDR] = 0x13;
+        rom[ROM_SIZE_ADDR] = 0x01;
+        rom[RAM_SIZE_ADDR] = 0x03;
+        rom
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header = CartridgeHeader::parse(&test_rom()).unwrap();
+
+        assert!(header.logo_valid);
+        assert_eq!(header.title, "RUSTEM");
+        assert_eq!(header.manufacturer_code, None);
+        assert_eq!(header.cartridge_type.mapper, Some(MapperKind::Mbc3));
+        assert!(header.cartridge_type.has_battery);
+        assert!(!header.cartridge_type.has_timer);
+        assert_eq!(header.rom_size(), Some(0x10000));
+        assert_eq!(header.ram_size(), Some(0x8000));
+        assert!(CartridgeHeader::parse(&[0u8; 0x100]).is_none());
+    }
+
+    #[test]
+    fn test_header_checksums() {
+        let mut rom = test_rom();
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert!(!header.header_checksum_valid());
+
+        rom[HEADER_CHECKSUM_ADDR] = header.computed_header_checksum;
+        let global = CartridgeHeader::parse(&rom)
+            .unwrap()
+            .computed_global_checksum;
+        rom[GLOBAL_CHECKSUM_ADDR..GLOBAL_CHECKSUM_ADDR + 2].copy_from_slice(&global.to_be_bytes());
+
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert!(header.header_checksum_valid());
+        assert!(header.global_checksum_valid());
+    }
+}
@@ -0,0 +1,42 @@
+use crate::console::cartridge::header::CartridgeType;
+use crate::console::constants::HEADER_END;
+use alloc::string::String;
+use core::fmt;
+
+/// Why a ROM could not be inserted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LoadError {
+    NotFound {
+        path: String,
+    },
+    /// The file exists but could not be read
+    Io {
+        path: String,
+        reason: String,
+    },
+    /// Shorter than the cartridge header at 0x100-0x14F, most likely not a Game Boy ROM
+    TruncatedHeader {
+        len: usize,
+    },
+    /// The header declares a banking chip that is not emulated
+    UnsupportedMapper(CartridgeType),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::NotFound { path } => write!(f, "ROM file not found: {}", path),
+            LoadError::Io { path, reason } => write!(f, "Failed to read {}: {}", path, reason),
+            LoadError::TruncatedHeader { len } => write!(
+                f,
+                "ROM is too short to hold a header ({} bytes, at least {} needed)",
+                len, HEADER_END
+            ),
+            LoadError::UnsupportedMapper(cartridge_type) => {
+                write!(f, "Unsupported cartridge type: {}", cartridge_type)
+            }
+        }
+    }
+}
+
+impl core::error::Error for LoadError {}
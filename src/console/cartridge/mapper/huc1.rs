@@ -0,0 +1,128 @@
+use crate::console::cartridge::mapper::mapper::Mapper;
+use crate::console::constants::*;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+const HUC1_IR_MODE: u8 = 0x0E;
+// No infrared light is ever seen by the receiver
+const HUC1_IR_NO_LIGHT: u8 = 0xC0;
+
+/// Hudson HuC1, MBC1 like banking with an infrared port mapped over the RAM area
+pub struct Huc1 {
+    ir_mode: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+}
+
+impl Huc1 {
+    pub fn new() -> Self {
+        Self {
+            ir_mode: false,
+            rom_bank: 1,
+            ram_bank: 0,
+        }
+    }
+
+    fn ram_addr(&self, addr: u16, ram_len: usize) -> usize {
+        let real_addr =
+            (self.ram_bank as usize * RAM_BANK_SIZE) | ((addr - EXT_RAM_BEGIN) as usize);
+        real_addr % ram_len
+    }
+}
+
+impl Mapper for Huc1 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        match addr {
+            ROM_BANK_0_BEGIN..=ROM_BANK_0_END => rom[addr as usize % rom.len()],
+            ROM_BANK_N_BEGIN..=ROM_BANK_N_END => {
+                let real_addr = (self.rom_bank as usize * ROM_BANK_SIZE)
+                    | ((addr - ROM_BANK_N_BEGIN) as usize);
+                rom[real_addr % rom.len()]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ir_mode = (value & 0x0F) == HUC1_IR_MODE;
+            }
+            0x2000..=0x3FFF => {
+                self.rom_bank = value & 0x3F;
+            }
+            0x4000..=0x5FFF => {
+                self.ram_bank = value & 0x03;
+            }
+            _ => {}
+        }
+    }
+
+    fn rom_bank(&self) -> usize {
+        self.rom_bank as usize
+    }
+
+    // HuC1 has no RAM enable, RAM is mapped whenever the IR port is not
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if self.ir_mode { return HUC1_IR_NO_LIGHT; }
+        if ram.is_empty() { return 0xFF; }
+        ram[self.ram_addr(addr, ram.len())]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) -> bool {
+        if self.ir_mode || ram.is_empty() { return false; }
+        ram[self.ram_addr(addr, ram.len())] = value;
+        true
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.ir_mode);
+        state.write_u8(self.rom_bank);
+        state.write_u8(self.ram_bank);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.ir_mode = state.read_bool()?;
+        self.rom_bank = state.read_u8()?;
+        self.ram_bank = state.read_u8()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::cartridge::mapper::huc1::*;
+    use crate::console::cartridge::mapper::mapper::test_utils::banked_rom;
+
+    #[test]
+    fn test_huc1_banking() {
+        let rom = banked_rom(0x40);
+        let mut ram = vec![0u8; 4 * RAM_BANK_SIZE];
+        let mut huc1 = Huc1::new();
+        assert_eq!(huc1.read_rom(&rom, ROM_BANK_N_BEGIN), 0x01);
+
+        huc1.write_rom(0x2000, 0x45);
+        assert_eq!(huc1.read_rom(&rom, ROM_BANK_N_BEGIN), 0x05, "only 6 bits should select");
+        assert_eq!(huc1.read_rom(&rom, ROM_BANK_0_BEGIN), 0x00);
+
+        huc1.write_rom(0x4000, 0x03);
+        assert!(huc1.write_ram(&mut ram, EXT_RAM_BEGIN + 1, 0x42));
+        assert_eq!(ram[3 * RAM_BANK_SIZE + 1], 0x42);
+        assert_eq!(huc1.read_ram(&ram, EXT_RAM_BEGIN + 1), 0x42);
+    }
+
+    #[test]
+    fn test_huc1_ir_mode() {
+        let mut ram = vec![0u8; RAM_BANK_SIZE];
+        let mut huc1 = Huc1::new();
+
+        huc1.write_rom(0x0000, HUC1_IR_MODE);
+        assert!(!huc1.write_ram(&mut ram, EXT_RAM_BEGIN, 0x42));
+        assert_eq!(ram[0], 0x00, "IR mode should ignore RAM writes");
+        assert_eq!(huc1.read_ram(&ram, EXT_RAM_BEGIN), HUC1_IR_NO_LIGHT);
+
+        huc1.write_rom(0x0000, 0x00);
+        assert!(huc1.write_ram(&mut ram, EXT_RAM_BEGIN, 0x42));
+        assert_eq!(huc1.read_ram(&ram, EXT_RAM_BEGIN), 0x42);
+    }
+}
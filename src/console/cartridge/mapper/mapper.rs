@@ -0,0 +1,85 @@
+use crate::console::cartridge::header::{CartridgeHeader, MapperKind};
+use crate::console::cartridge::mapper::huc1::Huc1;
+use crate::console::cartridge::mapper::mbc1::Mbc1;
+use crate::console::cartridge::mapper::mbc1m::Mbc1m;
+use crate::console::cartridge::mapper::mbc2::Mbc2;
+use crate::console::cartridge::mapper::mbc3::Mbc3;
+use crate::console::cartridge::mapper::mbc5::Mbc5;
+use crate::console::cartridge::mapper::rom_only::RomOnly;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Banking chip of a cartridge. ROM and RAM are owned by the cartridge and handed to the mapper
+/// on every access so each chip only has to keep track of its registers
+pub trait Mapper {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8;
+
+    fn write_rom(&mut self, addr: u16, value: u8);
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8;
+
+    /// Returns whether the value was stored, writes while RAM is disabled are dropped
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) -> bool;
+
+    /// Bank mapped at 0x4000-0x7FFF before wrapping around the ROM size
+    fn rom_bank(&self) -> usize {
+        1
+    }
+
+    /// Size of the RAM built into the chip itself, takes precedence over the header RAM size
+    fn builtin_ram_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Called every dot cycle
+    fn tick(&mut self) {}
+
+    fn motor_on(&self) -> bool {
+        false
+    }
+
+    /// Extra chip state appended after the RAM in battery saves
+    fn battery_footer(&self, _timestamp: u64) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn load_battery_footer(&mut self, _footer: &[u8], _timestamp: u64) {}
+
+    /// Banking registers and chip state for save states
+    fn save_state(&self, state: &mut StateWriter);
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), String>;
+}
+
+/// Picks the mapper from the cartridge type in the header, `None` if the chip is not emulated
+pub fn mapper_from_header(header: &CartridgeHeader, rom: &[u8]) -> Option<Box<dyn Mapper>> {
+    let cartridge_type = header.cartridge_type;
+    let mapper: Box<dyn Mapper> = match cartridge_type.mapper? {
+        MapperKind::RomOnly => Box::new(RomOnly::new()),
+        MapperKind::Mbc1 if Mbc1m::detect(rom) => Box::new(Mbc1m::new()),
+        MapperKind::Mbc1 => Box::new(Mbc1::new()),
+        MapperKind::Mbc2 => Box::new(Mbc2::new()),
+        MapperKind::Mbc3 => Box::new(Mbc3::new(cartridge_type.has_timer)),
+        MapperKind::Mbc5 => Box::new(Mbc5::new(cartridge_type.has_rumble)),
+        MapperKind::Huc1 => Box::new(Huc1::new()),
+        _ => return None,
+    };
+    Some(mapper)
+}
+
+#[cfg(test)]
+pub mod test_utils {
+    use crate::console::constants::ROM_BANK_SIZE;
+
+    /// Builds a ROM where the first two bytes of every bank hold its bank number
+    pub fn banked_rom(bank_count: usize) -> Vec<u8> {
+        let mut rom = vec![0u8; bank_count * ROM_BANK_SIZE];
+        for bank in 0..bank_count {
+            rom[bank * ROM_BANK_SIZE] = bank as u8;
+            rom[bank * ROM_BANK_SIZE + 1] = (bank >> 8) as u8;
+        }
+        rom
+    }
+}
@@ -0,0 +1,142 @@
+use crate::console::cartridge::mapper::mapper::Mapper;
+use crate::console::constants::*;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+// Multicarts wire the second bank register one bit lower, every game is 16 banks long
+const MBC1M_BANK_SHIFT: u8 = 4;
+const MBC1M_GAME_BANKS: usize = 0x10;
+const MBC1M_ROM_SIZE: usize = 0x100000;
+
+/// MBC1 wired for multicart compilations such as Mortal Kombat I & II or Bomberman Collection
+pub struct Mbc1m {
+    ram_enabled: bool,
+    rom_bank: u8,
+    ram_bank: u8,
+    banking_mode: u8,
+}
+
+impl Mbc1m {
+    pub fn new() -> Self {
+        Self {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            banking_mode: 0,
+        }
+    }
+
+    /// Multicarts are 1 MiB MBC1 ROMs with a second Nintendo logo in the header of the game
+    /// starting at bank 0x10
+    pub fn detect(rom: &[u8]) -> bool {
+        if rom.len() != MBC1M_ROM_SIZE {
+            return false;
+        }
+
+        let second_logo = MBC1M_GAME_BANKS * ROM_BANK_SIZE + NINTENDO_LOGO_ADDR;
+        rom[second_logo..second_logo + NINTENDO_LOGO.len()] == NINTENDO_LOGO
+    }
+
+    fn ram_addr(&self, addr: u16, ram_len: usize) -> usize {
+        let bank = if self.banking_mode == 1 { self.ram_bank as usize } else { 0 };
+        let real_addr = (bank * RAM_BANK_SIZE) | ((addr - EXT_RAM_BEGIN) as usize);
+        real_addr % ram_len
+    }
+}
+
+impl Mapper for Mbc1m {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        match addr {
+            ROM_BANK_0_BEGIN..=ROM_BANK_0_END => {
+                let bank = if self.banking_mode == 1 {
+                    (self.ram_bank << MBC1M_BANK_SHIFT) as usize
+                } else {
+                    0
+                };
+                let real_addr = (bank * ROM_BANK_SIZE) | (addr as usize);
+                rom[real_addr % rom.len()]
+            }
+            ROM_BANK_N_BEGIN..=ROM_BANK_N_END => {
+                let bank =
+                    ((self.ram_bank << MBC1M_BANK_SHIFT) | (self.rom_bank & 0x0F)) as usize;
+                let real_addr = (bank * ROM_BANK_SIZE) | ((addr - ROM_BANK_N_BEGIN) as usize);
+                rom[real_addr % rom.len()]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = (value & 0x0F) == 0x0A;
+            }
+            0x2000..=0x3FFF => {
+                // The zero check still looks at all 5 bits even though only 4 are wired
+                let mut bank = value & 0x1F;
+                if bank == 0 { bank = 1; }
+                self.rom_bank = bank;
+            }
+            0x4000..=0x5FFF => {
+                self.ram_bank = value & 0x03;
+            }
+            0x6000..=0x7FFF => {
+                self.banking_mode = value & 0x01;
+            }
+            _ => {}
+        }
+    }
+
+    fn rom_bank(&self) -> usize {
+        ((self.ram_bank << MBC1M_BANK_SHIFT) | (self.rom_bank & 0x0F)) as usize
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled || ram.is_empty() { return 0xFF; }
+        ram[self.ram_addr(addr, ram.len())]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) -> bool {
+        if !self.ram_enabled || ram.is_empty() { return false; }
+        ram[self.ram_addr(addr, ram.len())] = value;
+        true
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.ram_enabled);
+        state.write_u8(self.rom_bank);
+        state.write_u8(self.ram_bank);
+        state.write_u8(self.banking_mode);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.ram_enabled = state.read_bool()?;
+        self.rom_bank = state.read_u8()?;
+        self.ram_bank = state.read_u8()?;
+        self.banking_mode = state.read_u8()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::cartridge::mapper::mapper::test_utils::banked_rom;
+    use crate::console::cartridge::mapper::mbc1m::*;
+
+    #[test]
+    fn test_mbc1m_detect_and_banking() {
+        let mut rom = banked_rom(0x40);
+        let second_logo = MBC1M_GAME_BANKS * ROM_BANK_SIZE + NINTENDO_LOGO_ADDR;
+        assert!(!Mbc1m::detect(&rom));
+        rom[second_logo..second_logo + NINTENDO_LOGO.len()].copy_from_slice(&NINTENDO_LOGO);
+        assert!(Mbc1m::detect(&rom));
+
+        let mut mbc1m = Mbc1m::new();
+        mbc1m.write_rom(0x4000, 0x01);
+        mbc1m.write_rom(0x2000, 0x12);
+        assert_eq!(mbc1m.read_rom(&rom, ROM_BANK_N_BEGIN), 0x12);
+
+        mbc1m.write_rom(0x6000, 0x01);
+        assert_eq!(mbc1m.read_rom(&rom, ROM_BANK_0_BEGIN), 0x10);
+    }
+}
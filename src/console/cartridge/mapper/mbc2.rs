@@ -0,0 +1,122 @@
+use crate::console::cartridge::mapper::mapper::Mapper;
+use crate::console::constants::*;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+pub const MBC2_RAM_SIZE: usize = 0x200;
+// Bit 8 of the address picks between the RAM enable and ROM bank registers
+const MBC2_REGISTER_SELECT_BIT: u16 = 0x0100;
+
+pub struct Mbc2 {
+    ram_enabled: bool,
+    rom_bank: u8,
+}
+
+impl Mbc2 {
+    pub fn new() -> Self {
+        Self {
+            ram_enabled: false,
+            rom_bank: 1,
+        }
+    }
+
+    // The built-in 512 half-bytes are echoed across the whole external RAM area
+    fn ram_addr(addr: u16) -> usize {
+        (addr - EXT_RAM_BEGIN) as usize % MBC2_RAM_SIZE
+    }
+}
+
+impl Mapper for Mbc2 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        match addr {
+            ROM_BANK_0_BEGIN..=ROM_BANK_0_END => rom[addr as usize % rom.len()],
+            ROM_BANK_N_BEGIN..=ROM_BANK_N_END => {
+                let real_addr = (self.rom_bank as usize * ROM_BANK_SIZE)
+                    | ((addr - ROM_BANK_N_BEGIN) as usize);
+                rom[real_addr % rom.len()]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        if !(ROM_BANK_0_BEGIN..=ROM_BANK_0_END).contains(&addr) {
+            return;
+        }
+
+        if addr & MBC2_REGISTER_SELECT_BIT == 0 {
+            self.ram_enabled = (value & 0x0F) == 0x0A;
+        } else {
+            let mut bank = value & 0x0F;
+            if bank == 0 { bank = 1; }
+            self.rom_bank = bank;
+        }
+    }
+
+    fn rom_bank(&self) -> usize {
+        self.rom_bank as usize
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled { return 0xFF; }
+        // Only the lower nibble is wired, the upper one reads as open bus
+        ram[Self::ram_addr(addr)] | 0xF0
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) -> bool {
+        if !self.ram_enabled { return false; }
+        ram[Self::ram_addr(addr)] = value & 0x0F;
+        true
+    }
+
+    fn builtin_ram_size(&self) -> Option<usize> {
+        Some(MBC2_RAM_SIZE)
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.ram_enabled);
+        state.write_u8(self.rom_bank);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.ram_enabled = state.read_bool()?;
+        self.rom_bank = state.read_u8()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::cartridge::mapper::mbc2::*;
+
+    #[test]
+    fn test_mbc2_register_select() {
+        let mut mbc2 = Mbc2::new();
+
+        mbc2.write_rom(0x0100, 0x0A);
+        assert!(!mbc2.ram_enabled, "bit 8 set should not touch RAM enable");
+        assert_eq!(mbc2.rom_bank, 0x0A);
+
+        mbc2.write_rom(0x0000, 0x0A);
+        assert!(mbc2.ram_enabled);
+        assert_eq!(mbc2.rom_bank, 0x0A);
+
+        mbc2.write_rom(0x2100, 0x00);
+        assert_eq!(mbc2.rom_bank, 0x01, "bank 0 should map to bank 1");
+    }
+
+    #[test]
+    fn test_mbc2_nibble_ram_echo() {
+        let mut ram = [0u8; MBC2_RAM_SIZE];
+        let mut mbc2 = Mbc2::new();
+        assert!(!mbc2.write_ram(&mut ram, EXT_RAM_BEGIN + 0x10, 0xAB));
+        assert_eq!(ram[0x10], 0x00, "disabled RAM should ignore writes");
+        mbc2.write_rom(0x0000, 0x0A);
+
+        assert!(mbc2.write_ram(&mut ram, EXT_RAM_BEGIN + 0x10, 0xAB));
+        assert_eq!(ram[0x10], 0x0B);
+        assert_eq!(mbc2.read_ram(&ram, EXT_RAM_BEGIN + 0x10), 0xFB);
+        assert_eq!(mbc2.read_ram(&ram, EXT_RAM_BEGIN + 0x210), 0xFB);
+        assert_eq!(mbc2.read_ram(&ram, EXT_RAM_END - 0x1EF), 0xFB);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::console::cartridge::mapper::mapper::Mapper;
+use crate::console::cartridge::mapper::rtc::Rtc;
+use crate::console::constants::*;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const MBC3_RAM_BANK_COUNT: u8 = 4;
+
+pub struct Mbc3 {
+    ram_rtc_enabled: bool,
+    rom_bank: u8,
+    // 0x00-0x03 selects a RAM bank, 0x08-0x0C an RTC register
+    ram_rtc_select: u8,
+    last_latch_write: u8,
+    rtc: Option<Rtc>,
+}
+
+impl Mbc3 {
+    pub fn new(has_rtc: bool) -> Self {
+        Self {
+            ram_rtc_enabled: false,
+            rom_bank: 1,
+            ram_rtc_select: 0,
+            last_latch_write: 0xFF,
+            rtc: if has_rtc { Some(Rtc::new()) } else { None },
+        }
+    }
+
+    fn ram_addr(&self, addr: u16, ram_len: usize) -> usize {
+        let real_addr =
+            (self.ram_rtc_select as usize * RAM_BANK_SIZE) | ((addr - EXT_RAM_BEGIN) as usize);
+        real_addr % ram_len
+    }
+}
+
+impl Mapper for Mbc3 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        match addr {
+            ROM_BANK_0_BEGIN..=ROM_BANK_0_END => rom[addr as usize % rom.len()],
+            ROM_BANK_N_BEGIN..=ROM_BANK_N_END => {
+                let real_addr = (self.rom_bank as usize * ROM_BANK_SIZE)
+                    | ((addr - ROM_BANK_N_BEGIN) as usize);
+                rom[real_addr % rom.len()]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ram_rtc_enabled = (value & 0x0F) == 0x0A;
+            }
+            0x2000..=0x3FFF => {
+                let mut bank = value & 0x7F;
+                if bank == 0 { bank = 1; }
+                self.rom_bank = bank;
+            }
+            0x4000..=0x5FFF => {
+                self.ram_rtc_select = value;
+            }
+            0x6000..=0x7FFF => {
+                // Writing 0x00 then 0x01 latches the current time into the RTC registers
+                if self.last_latch_write == 0x00
+                    && value == 0x01
+                    && let Some(rtc) = self.rtc.as_mut()
+                {
+                    rtc.latch();
+                }
+                self.last_latch_write = value;
+            }
+            _ => {}
+        }
+    }
+
+    fn rom_bank(&self) -> usize {
+        self.rom_bank as usize
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_rtc_enabled { return 0xFF; }
+        match (self.ram_rtc_select, self.rtc.as_ref()) {
+            (bank, _) if bank < MBC3_RAM_BANK_COUNT => {
+                if ram.is_empty() { return 0xFF; }
+                ram[self.ram_addr(addr, ram.len())]
+            }
+            (reg, Some(rtc)) if Rtc::supported_reg(reg) => rtc.read(reg),
+            _ => 0xFF,
+        }
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) -> bool {
+        if !self.ram_rtc_enabled { return false; }
+        match self.ram_rtc_select {
+            bank if bank < MBC3_RAM_BANK_COUNT => {
+                if ram.is_empty() { return false; }
+                ram[self.ram_addr(addr, ram.len())] = value;
+                true
+            }
+            // The clock is part of the battery save as well
+            reg if Rtc::supported_reg(reg) => match self.rtc.as_mut() {
+                Some(rtc) => {
+                    rtc.write(reg, value);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+
+    fn tick(&mut self) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.tick();
+        }
+    }
+
+    fn battery_footer(&self, timestamp: u64) -> Option<Vec<u8>> {
+        self.rtc.as_ref().map(|rtc| rtc.save_footer(timestamp).to_vec())
+    }
+
+    fn load_battery_footer(&mut self, footer: &[u8], timestamp: u64) {
+        if let Some(rtc) = self.rtc.as_mut() {
+            rtc.load_footer(footer, timestamp);
+        }
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.ram_rtc_enabled);
+        state.write_u8(self.rom_bank);
+        state.write_u8(self.ram_rtc_select);
+        state.write_u8(self.last_latch_write);
+        if let Some(rtc) = &self.rtc {
+            rtc.save_state(state);
+        }
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.ram_rtc_enabled = state.read_bool()?;
+        self.rom_bank = state.read_u8()?;
+        self.ram_rtc_select = state.read_u8()?;
+        self.last_latch_write = state.read_u8()?;
+        if let Some(rtc) = &mut self.rtc {
+            rtc.load_state(state)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::cartridge::mapper::mapper::test_utils::banked_rom;
+    use crate::console::cartridge::mapper::mbc3::*;
+    use crate::console::cartridge::mapper::rtc::RTC_SECONDS;
+
+    #[test]
+    fn test_mbc3_rom_bank() {
+        let rom = banked_rom(0x80);
+        let mut mbc3 = Mbc3::new(false);
+        assert_eq!(mbc3.read_rom(&rom, ROM_BANK_N_BEGIN), 0x01);
+
+        mbc3.write_rom(0x2000, 0x00);
+        assert_eq!(mbc3.read_rom(&rom, ROM_BANK_N_BEGIN), 0x01, "bank 0 should map to bank 1");
+
+        mbc3.write_rom(0x3FFF, 0x7F);
+        assert_eq!(mbc3.read_rom(&rom, ROM_BANK_N_BEGIN), 0x7F);
+
+        mbc3.write_rom(0x2000, 0x85);
+        assert_eq!(mbc3.read_rom(&rom, ROM_BANK_N_BEGIN), 0x05, "only 7 bits should select");
+        assert_eq!(mbc3.read_rom(&rom, ROM_BANK_0_BEGIN), 0x00);
+    }
+
+    #[test]
+    fn test_mbc3_ram_bank_and_rtc_select() {
+        let mut ram = vec![0u8; 4 * RAM_BANK_SIZE];
+        let mut mbc3 = Mbc3::new(true);
+        assert!(!mbc3.write_ram(&mut ram, EXT_RAM_BEGIN, 0x42), "RAM should start disabled");
+        mbc3.write_rom(0x0000, 0x0A);
+
+        mbc3.write_rom(0x4000, 0x02);
+        assert!(mbc3.write_ram(&mut ram, EXT_RAM_BEGIN, 0x42));
+        assert_eq!(ram[2 * RAM_BANK_SIZE], 0x42);
+
+        mbc3.write_rom(0x4000, RTC_SECONDS);
+        assert!(mbc3.write_ram(&mut ram, EXT_RAM_BEGIN, 30));
+        assert_eq!(ram[2 * RAM_BANK_SIZE], 0x42, "RTC writes should not reach RAM");
+        mbc3.write_rom(0x6000, 0x00);
+        mbc3.write_rom(0x6000, 0x01);
+        assert_eq!(mbc3.read_ram(&ram, EXT_RAM_BEGIN), 30);
+
+        mbc3.write_rom(0x4000, 0x02);
+        assert_eq!(mbc3.read_ram(&ram, EXT_RAM_BEGIN), 0x42);
+
+        mbc3.write_rom(0x0000, 0x00);
+        assert_eq!(mbc3.read_ram(&ram, EXT_RAM_BEGIN), 0xFF);
+    }
+
+    #[test]
+    fn test_mbc3_latch_sequence() {
+        let ram = vec![0u8; RAM_BANK_SIZE];
+        let mut mbc3 = Mbc3::new(true);
+        mbc3.write_rom(0x0000, 0x0A);
+        mbc3.write_rom(0x4000, RTC_SECONDS);
+        mbc3.write_ram(&mut [], EXT_RAM_BEGIN, 10);
+
+        mbc3.write_rom(0x6000, 0x01);
+        mbc3.write_rom(0x6000, 0x01);
+        assert_eq!(mbc3.read_ram(&ram, EXT_RAM_BEGIN), 0, "0x01 alone should not latch");
+
+        mbc3.write_rom(0x6000, 0x00);
+        mbc3.write_rom(0x6000, 0x02);
+        mbc3.write_rom(0x6000, 0x01);
+        assert_eq!(mbc3.read_ram(&ram, EXT_RAM_BEGIN), 0, "0x01 should directly follow 0x00");
+
+        mbc3.write_rom(0x6000, 0x00);
+        mbc3.write_rom(0x6000, 0x01);
+        assert_eq!(mbc3.read_ram(&ram, EXT_RAM_BEGIN), 10);
+    }
+}
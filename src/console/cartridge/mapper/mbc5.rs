@@ -0,0 +1,142 @@
+use crate::console::cartridge::mapper::mapper::Mapper;
+use crate::console::constants::*;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+const MBC5_RUMBLE_MOTOR_BIT: u8 = 0b0000_1000;
+
+pub struct Mbc5 {
+    ram_enabled: bool,
+    rom_bank: u16,
+    ram_bank: u8,
+    has_rumble: bool,
+    motor_on: bool,
+}
+
+impl Mbc5 {
+    pub fn new(has_rumble: bool) -> Self {
+        Self {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_bank: 0,
+            has_rumble,
+            motor_on: false,
+        }
+    }
+
+    fn ram_addr(&self, addr: u16, ram_len: usize) -> usize {
+        let real_addr =
+            (self.ram_bank as usize * RAM_BANK_SIZE) | ((addr - EXT_RAM_BEGIN) as usize);
+        real_addr % ram_len
+    }
+}
+
+impl Mapper for Mbc5 {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        match addr {
+            ROM_BANK_0_BEGIN..=ROM_BANK_0_END => rom[addr as usize % rom.len()],
+            ROM_BANK_N_BEGIN..=ROM_BANK_N_END => {
+                // Unlike MBC1/MBC3, bank 0 can be mapped here
+                let real_addr = (self.rom_bank as usize * ROM_BANK_SIZE)
+                    | ((addr - ROM_BANK_N_BEGIN) as usize);
+                rom[real_addr % rom.len()]
+            }
+            _ => 0xFF,
+        }
+    }
+
+    fn write_rom(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => {
+                self.ram_enabled = (value & 0x0F) == 0x0A;
+            }
+            0x2000..=0x2FFF => {
+                self.rom_bank = (self.rom_bank & 0x100) | value as u16;
+            }
+            0x3000..=0x3FFF => {
+                self.rom_bank = (self.rom_bank & 0xFF) | (((value & 0x01) as u16) << 8);
+            }
+            0x4000..=0x5FFF => {
+                if self.has_rumble {
+                    // Bit 3 drives the motor instead of selecting a RAM bank
+                    self.motor_on = value & MBC5_RUMBLE_MOTOR_BIT != 0;
+                    self.ram_bank = value & 0x07;
+                } else {
+                    self.ram_bank = value & 0x0F;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn rom_bank(&self) -> usize {
+        self.rom_bank as usize
+    }
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if !self.ram_enabled || ram.is_empty() { return 0xFF; }
+        ram[self.ram_addr(addr, ram.len())]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) -> bool {
+        if !self.ram_enabled || ram.is_empty() { return false; }
+        ram[self.ram_addr(addr, ram.len())] = value;
+        true
+    }
+
+    fn motor_on(&self) -> bool {
+        self.motor_on
+    }
+
+    fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.ram_enabled);
+        state.write_u16(self.rom_bank);
+        state.write_u8(self.ram_bank);
+        state.write_bool(self.motor_on);
+    }
+
+    fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.ram_enabled = state.read_bool()?;
+        self.rom_bank = state.read_u16()?;
+        self.ram_bank = state.read_u8()?;
+        self.motor_on = state.read_bool()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::cartridge::mapper::mapper::test_utils::banked_rom;
+    use crate::console::cartridge::mapper::mbc5::*;
+
+    #[test]
+    fn test_mbc5_9_bit_rom_bank() {
+        let rom = banked_rom(0x200);
+        let mut mbc5 = Mbc5::new(false);
+
+        mbc5.write_rom(0x2000, 0x23);
+        mbc5.write_rom(0x3000, 0x01);
+        assert_eq!(mbc5.read_rom(&rom, ROM_BANK_N_BEGIN), 0x23);
+        assert_eq!(mbc5.read_rom(&rom, ROM_BANK_N_BEGIN + 1), 0x01);
+
+        mbc5.write_rom(0x2000, 0x00);
+        mbc5.write_rom(0x3000, 0x00);
+        assert_eq!(mbc5.read_rom(&rom, ROM_BANK_N_BEGIN), 0x00, "bank 0 should be selectable");
+    }
+
+    #[test]
+    fn test_mbc5_rumble_motor() {
+        let mut ram = vec![0u8; 8 * RAM_BANK_SIZE];
+        let mut mbc5 = Mbc5::new(true);
+        mbc5.write_rom(0x0000, 0x0A);
+
+        mbc5.write_rom(0x4000, MBC5_RUMBLE_MOTOR_BIT | 0x02);
+        assert!(mbc5.motor_on());
+
+        mbc5.write_ram(&mut ram, EXT_RAM_BEGIN, 0x42);
+        assert_eq!(ram[2 * RAM_BANK_SIZE], 0x42, "motor bit should not select a RAM bank");
+
+        mbc5.write_rom(0x4000, 0x02);
+        assert!(!mbc5.motor_on());
+    }
+}
@@ -0,0 +1,9 @@
+pub mod mapper;
+mod huc1;
+mod mbc1;
+mod mbc1m;
+mod mbc2;
+mod mbc3;
+mod mbc5;
+pub mod rom_only;
+mod rtc;
@@ -0,0 +1,63 @@
+use crate::console::cartridge::mapper::mapper::Mapper;
+use crate::console::constants::*;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+/// 32 KiB cartridges without a banking chip, optionally with up to 8 KiB of RAM
+pub struct RomOnly {}
+
+impl RomOnly {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+impl Mapper for RomOnly {
+    fn read_rom(&self, rom: &[u8], addr: u16) -> u8 {
+        rom[addr as usize % rom.len()]
+    }
+
+    fn write_rom(&mut self, _addr: u16, _value: u8) {}
+
+    fn read_ram(&self, ram: &[u8], addr: u16) -> u8 {
+        if ram.is_empty() { return 0xFF; }
+        ram[(addr - EXT_RAM_BEGIN) as usize % ram.len()]
+    }
+
+    fn write_ram(&mut self, ram: &mut [u8], addr: u16, value: u8) -> bool {
+        if ram.is_empty() { return false; }
+        let len = ram.len();
+        ram[(addr - EXT_RAM_BEGIN) as usize % len] = value;
+        true
+    }
+
+    fn save_state(&self, _state: &mut StateWriter) {}
+
+    fn load_state(&mut self, _state: &mut StateReader) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::cartridge::mapper::mapper::test_utils::banked_rom;
+    use crate::console::cartridge::mapper::rom_only::*;
+
+    #[test]
+    fn test_rom_only_ignores_writes() {
+        let rom = banked_rom(2);
+        let mut rom_only = RomOnly::new();
+
+        rom_only.write_rom(0x2000, 0x05);
+        rom_only.write_rom(0x4000, 0x01);
+        assert_eq!(rom_only.read_rom(&rom, ROM_BANK_N_BEGIN), 0x01, "there is no bank switching");
+        assert_eq!(rom_only.read_rom(&rom, ROM_BANK_0_BEGIN), 0x00);
+
+        assert!(!rom_only.write_ram(&mut [], EXT_RAM_BEGIN, 0x42));
+        assert_eq!(rom_only.read_ram(&[], EXT_RAM_BEGIN), 0xFF);
+
+        let mut ram = vec![0u8; RAM_BANK_SIZE];
+        assert!(rom_only.write_ram(&mut ram, EXT_RAM_BEGIN + 1, 0x42));
+        assert_eq!(rom_only.read_ram(&ram, EXT_RAM_BEGIN + 1), 0x42);
+    }
+}
@@ -0,0 +1,287 @@
+use crate::console::constants::DOT_CYCLES_PER_SECOND;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
+pub const RTC_SECONDS: u8 = 0x08;
+pub const RTC_MINUTES: u8 = 0x09;
+pub const RTC_HOURS: u8 = 0x0A;
+pub const RTC_DAY_LOW: u8 = 0x0B;
+pub const RTC_DAY_HIGH: u8 = 0x0C;
+
+#[repr(u8)]
+pub enum RtcDayHighFlag {
+    DayBit8 = 0b0000_0001,
+    Halt = 0b0100_0000,
+    DayCarry = 0b1000_0000,
+}
+
+const RTC_REG_COUNT: usize = 5;
+const DAY_COUNTER_LIMIT: u16 = 0x200;
+
+// Layout shared by VBA-M, BGB, mGBA and SameBoy: live and latched registers as 32 bit LE words
+// followed by a 64 bit UNIX timestamp. Older saves use a 32 bit timestamp
+pub const RTC_SAVE_FOOTER_SIZE: usize = 48;
+const RTC_SAVE_FOOTER_SIZE_LEGACY: usize = 44;
+const RTC_SAVE_TIMESTAMP_OFFSET: usize = 40;
+
+/// MBC3 real time clock, counts seconds with the emulated dot clock
+#[derive(Default)]
+pub struct Rtc {
+    seconds: u8,
+    minutes: u8,
+    hours: u8,
+    days: u16,
+    halted: bool,
+    day_carry: bool,
+    latched: [u8; RTC_REG_COUNT],
+    dot_cycles: u64,
+}
+
+impl Rtc {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn supported_reg(reg: u8) -> bool {
+        (RTC_SECONDS..=RTC_DAY_HIGH).contains(&reg)
+    }
+
+    pub fn tick(&mut self) {
+        if self.halted {
+            return;
+        }
+
+        self.dot_cycles += 1;
+        if self.dot_cycles >= DOT_CYCLES_PER_SECOND {
+            self.dot_cycles -= DOT_CYCLES_PER_SECOND;
+            self.advance_second();
+        }
+    }
+
+    // Counters are 6/6/5 bits wide, an out of range value written by the game keeps counting
+    // until the register overflows and wraps to 0 without carrying into the next one
+    fn advance_second(&mut self) {
+        self.seconds = (self.seconds + 1) & 0x3F;
+        if self.seconds != 60 {
+            return;
+        }
+        self.seconds = 0;
+
+        self.minutes = (self.minutes + 1) & 0x3F;
+        if self.minutes != 60 {
+            return;
+        }
+        self.minutes = 0;
+
+        self.hours = (self.hours + 1) & 0x1F;
+        if self.hours != 24 {
+            return;
+        }
+        self.hours = 0;
+
+        self.days += 1;
+        if self.days == DAY_COUNTER_LIMIT {
+            self.days = 0;
+            self.day_carry = true;
+        }
+    }
+
+    fn day_high(&self) -> u8 {
+        let mut value = ((self.days >> 8) as u8) & RtcDayHighFlag::DayBit8 as u8;
+        if self.halted {
+            value |= RtcDayHighFlag::Halt as u8;
+        }
+        if self.day_carry {
+            value |= RtcDayHighFlag::DayCarry as u8;
+        }
+        value
+    }
+
+    fn live_regs(&self) -> [u8; RTC_REG_COUNT] {
+        [
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.days as u8,
+            self.day_high(),
+        ]
+    }
+
+    pub fn latch(&mut self) {
+        self.latched = self.live_regs();
+    }
+
+    // Catch up on time passed while the emulator was not running
+    fn advance_seconds(&mut self, elapsed: u64) {
+        let total = self.seconds as u64
+            + self.minutes as u64 * 60
+            + self.hours as u64 * 3600
+            + self.days as u64 * 86400
+            + elapsed;
+        let days = total / 86400;
+
+        self.seconds = (total % 60) as u8;
+        self.minutes = (total / 60 % 60) as u8;
+        self.hours = (total / 3600 % 24) as u8;
+        self.days = (days % DAY_COUNTER_LIMIT as u64) as u16;
+        if days >= DAY_COUNTER_LIMIT as u64 {
+            self.day_carry = true;
+        }
+    }
+
+    pub fn save_footer(&self, timestamp: u64) -> [u8; RTC_SAVE_FOOTER_SIZE] {
+        let mut footer = [0u8; RTC_SAVE_FOOTER_SIZE];
+        let regs = self.live_regs().into_iter().chain(self.latched);
+        for (i, reg) in regs.enumerate() {
+            footer[i * 4..i * 4 + 4].copy_from_slice(&(reg as u32).to_le_bytes());
+        }
+        footer[RTC_SAVE_TIMESTAMP_OFFSET..].copy_from_slice(&timestamp.to_le_bytes());
+        footer
+    }
+
+    pub fn load_footer(&mut self, footer: &[u8], timestamp: u64) {
+        let saved_timestamp = match footer.len() {
+            RTC_SAVE_FOOTER_SIZE => u64::from_le_bytes(
+                footer[RTC_SAVE_TIMESTAMP_OFFSET..].try_into().unwrap(),
+            ),
+            RTC_SAVE_FOOTER_SIZE_LEGACY => u32::from_le_bytes(
+                footer[RTC_SAVE_TIMESTAMP_OFFSET..].try_into().unwrap(),
+            ) as u64,
+            _ => return,
+        };
+
+        let word = |i: usize| footer[i * 4];
+        self.write(RTC_SECONDS, word(0));
+        self.write(RTC_MINUTES, word(1));
+        self.write(RTC_HOURS, word(2));
+        self.write(RTC_DAY_LOW, word(3));
+        self.write(RTC_DAY_HIGH, word(4));
+        for i in 0..RTC_REG_COUNT {
+            self.latched[i] = word(RTC_REG_COUNT + i);
+        }
+
+        if !self.halted && timestamp > saved_timestamp {
+            self.advance_seconds(timestamp - saved_timestamp);
+        }
+    }
+
+    /// Unlike the battery footer this restores the emulated clock as is, without catching up
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.seconds);
+        state.write_u8(self.minutes);
+        state.write_u8(self.hours);
+        state.write_u16(self.days);
+        state.write_bool(self.halted);
+        state.write_bool(self.day_carry);
+        state.write_bytes(&self.latched);
+        state.write_u64(self.dot_cycles);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.seconds = state.read_u8()?;
+        self.minutes = state.read_u8()?;
+        self.hours = state.read_u8()?;
+        self.days = state.read_u16()?;
+        self.halted = state.read_bool()?;
+        self.day_carry = state.read_bool()?;
+        state.read_into(&mut self.latched)?;
+        self.dot_cycles = state.read_u64()?;
+        Ok(())
+    }
+
+    pub fn read(&self, reg: u8) -> u8 {
+        debug_assert!(Rtc::supported_reg(reg));
+        self.latched[(reg - RTC_SECONDS) as usize]
+    }
+
+    pub fn write(&mut self, reg: u8, value: u8) {
+        match reg {
+            RTC_SECONDS => {
+                self.seconds = value & 0x3F;
+                // Writing the seconds resets the sub-second divider
+                self.dot_cycles = 0;
+            }
+            RTC_MINUTES => self.minutes = value & 0x3F,
+            RTC_HOURS => self.hours = value & 0x1F,
+            RTC_DAY_LOW => self.days = (self.days & 0x100) | value as u16,
+            RTC_DAY_HIGH => {
+                self.days = (self.days & 0xFF)
+                    | (((value & RtcDayHighFlag::DayBit8 as u8) as u16) << 8);
+                self.halted = value & (RtcDayHighFlag::Halt as u8) != 0;
+                self.day_carry = value & (RtcDayHighFlag::DayCarry as u8) != 0;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::cartridge::mapper::rtc::*;
+
+    fn tick_seconds(rtc: &mut Rtc, seconds: u64) {
+        for _ in 0..seconds * DOT_CYCLES_PER_SECOND {
+            rtc.tick();
+        }
+    }
+
+    #[test]
+    fn test_rtc_reads_latched_value() {
+        let mut rtc = Rtc::new();
+        tick_seconds(&mut rtc, 2);
+        assert_eq!(rtc.read(RTC_SECONDS), 0, "registers should not change until latched");
+
+        rtc.latch();
+        assert_eq!(rtc.read(RTC_SECONDS), 2);
+
+        tick_seconds(&mut rtc, 1);
+        assert_eq!(rtc.read(RTC_SECONDS), 2, "latched registers should stay frozen");
+    }
+
+    #[test]
+    fn test_rtc_day_carry() {
+        let mut rtc = Rtc::new();
+        rtc.write(RTC_DAY_LOW, 0xFF);
+        rtc.write(RTC_DAY_HIGH, RtcDayHighFlag::DayBit8 as u8);
+        rtc.write(RTC_HOURS, 23);
+        rtc.write(RTC_MINUTES, 59);
+        rtc.write(RTC_SECONDS, 59);
+
+        tick_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(rtc.read(RTC_SECONDS), 0);
+        assert_eq!(rtc.read(RTC_MINUTES), 0);
+        assert_eq!(rtc.read(RTC_HOURS), 0);
+        assert_eq!(rtc.read(RTC_DAY_LOW), 0);
+        assert_eq!(rtc.read(RTC_DAY_HIGH), RtcDayHighFlag::DayCarry as u8);
+    }
+
+    #[test]
+    fn test_rtc_save_footer_catches_up() {
+        let mut rtc = Rtc::new();
+        rtc.write(RTC_HOURS, 23);
+        rtc.write(RTC_MINUTES, 59);
+        let footer = rtc.save_footer(1_000);
+
+        let mut loaded = Rtc::new();
+        loaded.load_footer(&footer, 1_000 + 61);
+        loaded.latch();
+
+        assert_eq!(loaded.read(RTC_SECONDS), 1);
+        assert_eq!(loaded.read(RTC_MINUTES), 0);
+        assert_eq!(loaded.read(RTC_HOURS), 0);
+        assert_eq!(loaded.read(RTC_DAY_LOW), 1);
+    }
+
+    #[test]
+    fn test_rtc_halt() {
+        let mut rtc = Rtc::new();
+        rtc.write(RTC_DAY_HIGH, RtcDayHighFlag::Halt as u8);
+        tick_seconds(&mut rtc, 1);
+        rtc.latch();
+
+        assert_eq!(rtc.read(RTC_SECONDS), 0);
+        assert_eq!(rtc.read(RTC_DAY_HIGH), RtcDayHighFlag::Halt as u8);
+    }
+}
@@ -0,0 +1,4 @@
+pub mod cartridge;
+pub mod header;
+pub mod load_error;
+mod mapper;
@@ -1,7 +1,7 @@
 pub const MEMORY_SIZE: usize = 0x10000;
 
-pub const CARTRIDGE_SIZE: usize = 0x80000;
-pub const MAX_RAM_SIZE: usize = 0x8000;
+pub const MIN_ROM_SIZE: usize = 0x8000;
+pub const MAX_RAM_SIZE: usize = 0x20000;
 
 pub const ROM_BANK_0_BEGIN: u16 = 0x0000;
 pub const ROM_BANK_0_END: u16 = 0x3FFF;
@@ -13,10 +13,33 @@ pub const EXT_RAM_END: u16 = 0xBFFF;
 pub const ROM_BANK_SIZE: usize = 0x4000; 
 pub const RAM_BANK_SIZE: usize = 0x2000; 
 
+pub const NINTENDO_LOGO_ADDR: usize = 0x0104;
+pub const NINTENDO_LOGO: [u8; 0x30] = [
+    0xCE, 0xED, 0x66, 0x66, 0xCC, 0x0D, 0x00, 0x0B, 0x03, 0x73, 0x00, 0x83, 0x00, 0x0C, 0x00, 0x0D,
+    0x00, 0x08, 0x11, 0x1F, 0x88, 0x89, 0x00, 0x0E, 0xDC, 0xCC, 0x6E, 0xE6, 0xDD, 0xDD, 0xD9, 0x99,
+    0xBB, 0xBB, 0x67, 0x63, 0x6E, 0x0E, 0xEC, 0xCC, 0xDD, 0xDC, 0x99, 0x9F, 0xBB, 0xB9, 0x33, 0x3E,
+];
+pub const TITLE_ADDR: usize = 0x0134;
+pub const MANUFACTURER_CODE_ADDR: usize = 0x013F;
+pub const CGB_FLAG_ADDR: usize = 0x0143;
+pub const NEW_LICENSEE_CODE_ADDR: usize = 0x0144;
+pub const SGB_FLAG_ADDR: usize = 0x0146;
+pub const CARTRIDGE_TYPE_ADDR: usize = 0x0147;
+pub const ROM_SIZE_ADDR: usize = 0x0148;
+pub const RAM_SIZE_ADDR: usize = 0x0149;
+pub const DESTINATION_CODE_ADDR: usize = 0x014A;
+pub const OLD_LICENSEE_CODE_ADDR: usize = 0x014B;
+pub const VERSION_ADDR: usize = 0x014C;
+pub const HEADER_CHECKSUM_ADDR: usize = 0x014D;
+pub const GLOBAL_CHECKSUM_ADDR: usize = 0x014E;
+pub const HEADER_END: usize = 0x0150;
+pub const USE_NEW_LICENSEE_CODE: u8 = 0x33;
+
 pub const TIMER_DIV_INC_RATE: u64 = 0x100;
 pub const REG_COUNT: usize = 42;
 pub const OAM_SCAN_DOT_LENGTH: u64 = 80;
 pub const FRAME_DOT_CYCLES: u64 = 70224;
+pub const DOT_CYCLES_PER_SECOND: u64 = 4_194_304;
 pub const NUMBER_SCANLINES: u64 = 154;
 pub const DOTS_PER_SCANLINE: u64 = 456;
 pub const DMA_MULT: u16 = 0x100;
@@ -44,6 +67,15 @@ pub const SCREEN_WIDTH: usize = 160;
 pub const SCREEN_HEIGHT: usize = 144;
 pub const BUFFER_SIZE: usize = SCREEN_WIDTH * SCREEN_HEIGHT;
 
+pub const SERIAL_SB_ADDR: u16 = 0xFF01;
+pub const SERIAL_SC_ADDR: u16 = 0xFF02;
+
+pub const AUDIO_REGS_BEGIN: u16 = 0xFF10;
+pub const AUDIO_REGS_END: u16 = 0xFF3F;
+pub const WAVE_RAM_BEGIN: u16 = 0xFF30;
+pub const WAVE_RAM_SIZE: usize = 0x10;
+pub const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+
 pub const BOOT_ROM_DISABLE_ADDR: u16 = 0xFF50;
 pub const BOOT_ROM_SIZE: usize = 0x100;
 pub const BOOT_ROM: [u8; BOOT_ROM_SIZE] = [
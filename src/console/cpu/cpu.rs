@@ -1,6 +1,10 @@
 use crate::console::bus::*;
 use crate::console::cpu::instruction::*;
+use crate::console::debugger::Registers;
+use crate::console::save_state::{StateReader, StateWriter};
 use crate::console::utils::bit_utils;
+use alloc::format;
+use alloc::string::String;
 
 #[derive(Default)]
 pub struct Cpu {
@@ -46,6 +50,83 @@ impl Cpu {
         Self::default()
     }
 
+    /// Jumps to `pc` from outside the program, for entry points other than the boot ROM
+    pub fn set_pc(&mut self, pc: u16) {
+        self._pc = pc;
+    }
+
+    pub fn registers(&self) -> Registers {
+        Registers {
+            a: self._a,
+            f: self._f,
+            b: self._b,
+            c: self._c,
+            d: self._d,
+            e: self._e,
+            h: self._h,
+            l: self._l,
+            sp: self._sp,
+            pc: self._pc,
+            ime: self._interrupts_enabled,
+            halted: self._halted,
+        }
+    }
+
+    pub fn set_registers(&mut self, registers: Registers) {
+        self._a = registers.a;
+        self._f = registers.f & 0xf0;
+        self._b = registers.b;
+        self._c = registers.c;
+        self._d = registers.d;
+        self._e = registers.e;
+        self._h = registers.h;
+        self._l = registers.l;
+        self._sp = registers.sp;
+        self._pc = registers.pc;
+        self._interrupts_enabled = registers.ime;
+        self._halted = registers.halted;
+    }
+
+    /// Text of the instruction at `addr` and its size in bytes
+    pub fn disassemble(bus: &Bus, addr: u16) -> (String, u16) {
+        let (instruction, size) = Instruction::decode(
+            bus.peek(addr),
+            bus.peek(addr.wrapping_add(1)),
+            bus.peek(addr.wrapping_add(2)),
+        );
+        (format!("{}", instruction), size)
+    }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        for register in [self._a, self._b, self._c, self._d, self._e, self._f, self._h, self._l] {
+            state.write_u8(register);
+        }
+        state.write_u16(self._sp);
+        state.write_u16(self._pc);
+        state.write_bool(self._interrupts_enabled);
+        state.write_bool(self._previous_instruction_was_ei);
+        state.write_bool(self._halted);
+        state.write_bool(self._halt_bug_triggered);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self._a = state.read_u8()?;
+        self._b = state.read_u8()?;
+        self._c = state.read_u8()?;
+        self._d = state.read_u8()?;
+        self._e = state.read_u8()?;
+        self._f = state.read_u8()? & 0xf0;
+        self._h = state.read_u8()?;
+        self._l = state.read_u8()?;
+        self._sp = state.read_u16()?;
+        self._pc = state.read_u16()?;
+        self._interrupts_enabled = state.read_bool()?;
+        self._previous_instruction_was_ei = state.read_bool()?;
+        self._halted = state.read_bool()?;
+        self._halt_bug_triggered = state.read_bool()?;
+        Ok(())
+    }
+
     // Utility
 
     fn set_register(&mut self, register: Register, value: u8) {
@@ -1094,9 +1175,9 @@ impl Cpu {
     // Decode/Fetch/Execute helpers
 
     fn decode_instruction_at_pc(&self, bus: &Bus) -> (Instruction, u16) {
-        let first_byte = bus.read_from_8b(self._pc);
-        let second_byte = bus.read_from_8b(self._pc.wrapping_add(1));
-        let third_byte = bus.read_from_8b(self._pc.wrapping_add(2));
+        let first_byte = bus.peek(self._pc);
+        let second_byte = bus.peek(self._pc.wrapping_add(1));
+        let third_byte = bus.peek(self._pc.wrapping_add(2));
         Instruction::decode(first_byte, second_byte, third_byte)
     }
 
@@ -0,0 +1,216 @@
+use crate::console::constants::{ROM_BANK_0_BEGIN, ROM_BANK_N_BEGIN, ROM_BANK_N_END};
+use core::fmt;
+
+// Flag bits of the F register
+const FLAG_Z: u8 = 0x80;
+const FLAG_N: u8 = 0x40;
+const FLAG_H: u8 = 0x20;
+const FLAG_C: u8 = 0x10;
+
+/// Stops before the instruction at `addr` runs. A bank only matters for ROM addresses, where it
+/// has to be the one currently mapped
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Breakpoint {
+    pub bank: Option<u16>,
+    pub addr: u16,
+}
+
+impl Breakpoint {
+    pub fn new(addr: u16) -> Self {
+        Self { bank: None, addr }
+    }
+
+    pub fn in_bank(bank: u16, addr: u16) -> Self {
+        Self {
+            bank: Some(bank),
+            addr,
+        }
+    }
+
+    /// `rom_bank` is the bank mapped at 0x4000-0x7FFF
+    pub fn matches(&self, pc: u16, rom_bank: u16) -> bool {
+        if self.addr != pc {
+            return false;
+        }
+        match (self.bank, pc) {
+            (Some(bank), ROM_BANK_0_BEGIN..ROM_BANK_N_BEGIN) => bank == 0,
+            (Some(bank), ROM_BANK_N_BEGIN..=ROM_BANK_N_END) => bank == rom_bank,
+            _ => true,
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.bank {
+            Some(bank) => write!(f, "{:02X}:{:04X}", bank, self.addr),
+            None => write!(f, "{:04X}", self.addr),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum WatchKind {
+    Read,
+    Write,
+    Access,
+}
+
+/// Stops after an instruction that accessed `start..=end`. Opcode fetches do not count as reads
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Watchpoint {
+    pub kind: WatchKind,
+    pub start: u16,
+    pub end: u16,
+}
+
+impl Watchpoint {
+    pub(crate) fn matches(&self, write: bool, addr: u16) -> bool {
+        let kind = match self.kind {
+            WatchKind::Read => !write,
+            WatchKind::Write => write,
+            WatchKind::Access => true,
+        };
+        kind && (self.start..=self.end).contains(&addr)
+    }
+}
+
+impl fmt::Display for Watchpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            WatchKind::Read => "r",
+            WatchKind::Write => "w",
+            WatchKind::Access => "rw",
+        };
+        write!(f, "{} {:04X}", kind, self.start)?;
+        if self.end != self.start {
+            write!(f, "-{:04X}", self.end)?;
+        }
+        Ok(())
+    }
+}
+
+/// First access to a watched address during an instruction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WatchHit {
+    pub write: bool,
+    pub addr: u16,
+    pub value: u8,
+}
+
+impl fmt::Display for WatchHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access = if self.write { "write" } else { "read" };
+        write!(f, "{} {:02X} at {:04X}", access, self.value, self.addr)
+    }
+}
+
+/// Temporary stop for stepping over calls and out of functions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RunTo {
+    /// Back at `pc` with the stack no deeper than `sp`, after a call returned
+    Return { pc: u16, sp: u16 },
+    /// The stack pointer rose above `sp`, the current function returned
+    StackAbove(u16),
+}
+
+impl RunTo {
+    pub(crate) fn reached(&self, pc: u16, sp: u16) -> bool {
+        match *self {
+            RunTo::Return {
+                pc: target,
+                sp: depth,
+            } => pc == target && sp >= depth,
+            RunTo::StackAbove(depth) => sp > depth,
+        }
+    }
+}
+
+/// Why the debug run loop handed control back
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DebugBreak {
+    Breakpoint(Breakpoint),
+    Watchpoint(WatchHit),
+    RunTo,
+}
+
+impl fmt::Display for DebugBreak {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugBreak::Breakpoint(breakpoint) => write!(f, "Breakpoint {}", breakpoint),
+            DebugBreak::Watchpoint(hit) => write!(f, "Watchpoint, {}", hit),
+            DebugBreak::RunTo => write!(f, "Stopped"),
+        }
+    }
+}
+
+/// Copy of the CPU registers
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Registers {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+    pub ime: bool,
+    pub halted: bool,
+}
+
+impl fmt::Display for Registers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let flag = |mask: u8, name: char| if self.f & mask != 0 { name } else { '-' };
+        writeln!(
+            f,
+            "AF={:02X}{:02X} BC={:02X}{:02X} DE={:02X}{:02X} HL={:02X}{:02X} SP={:04X} PC={:04X}",
+            self.a, self.f, self.b, self.c, self.d, self.e, self.h, self.l, self.sp, self.pc
+        )?;
+        write!(
+            f,
+            "Flags {}{}{}{} IME={}{}",
+            flag(FLAG_Z, 'Z'),
+            flag(FLAG_N, 'N'),
+            flag(FLAG_H, 'H'),
+            flag(FLAG_C, 'C'),
+            self.ime as u8,
+            if self.halted { " halted" } else { "" }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::debugger::*;
+
+    #[test]
+    fn test_breakpoint_bank() {
+        assert!(Breakpoint::new(0x4100).matches(0x4100, 3));
+        assert!(Breakpoint::in_bank(3, 0x4100).matches(0x4100, 3));
+        assert!(!Breakpoint::in_bank(2, 0x4100).matches(0x4100, 3));
+        assert!(Breakpoint::in_bank(0, 0x0150).matches(0x0150, 3));
+        assert!(!Breakpoint::in_bank(1, 0x0150).matches(0x0150, 3));
+        // Banks are ignored outside the ROM
+        assert!(Breakpoint::in_bank(5, 0xC000).matches(0xC000, 3));
+        assert!(!Breakpoint::new(0x4100).matches(0x4101, 3));
+    }
+
+    #[test]
+    fn test_registers_display() {
+        let registers = Registers {
+            a: 0x01,
+            f: 0xB0,
+            pc: 0x0150,
+            sp: 0xFFFE,
+            ime: true,
+            ..Registers::default()
+        };
+        assert_eq!(
+            registers.to_string(),
+            "AF=01B0 BC=0000 DE=0000 HL=0000 SP=FFFE PC=0150\nFlags Z-HC IME=1"
+        );
+    }
+}
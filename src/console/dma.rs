@@ -1,3 +1,6 @@
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
+
 #[derive(Default)]
 pub struct DMAData {
     pub running: bool,
@@ -13,4 +16,19 @@ impl DMAData {
         self.dot_cycle_since_start = 0;
         self.running = true;
     }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bool(self.running);
+        state.write_u16(self.start_addr);
+        state.write_u16(self.current_addr);
+        state.write_u16(self.dot_cycle_since_start);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.running = state.read_bool()?;
+        self.start_addr = state.read_u16()?;
+        self.current_addr = state.read_u16()?;
+        self.dot_cycle_since_start = state.read_u16()?;
+        Ok(())
+    }
 }
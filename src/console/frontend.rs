@@ -0,0 +1,62 @@
+use crate::console::constants::{DEFAULT_SAMPLE_RATE, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::console::gui::gpu::PixelLevel;
+
+/// Receives every finished frame. Frontends that run in real time also pace the emulation here
+pub trait VideoSink {
+    fn present(&mut self, frame: &[PixelLevel; SCREEN_WIDTH * SCREEN_HEIGHT]);
+
+    /// Short status text, like a save state that failed to load
+    fn show_message(&mut self, _message: &str) {}
+}
+
+/// Receives the sound mixed during each frame as (left, right) samples in -1.0..=1.0
+pub trait AudioSink {
+    /// Real-time sinks may block here until their buffer has room, which paces the emulation
+    fn queue_samples(&mut self, samples: &[[f32; 2]]);
+
+    /// Rate the core mixes at, read before every frame. Sinks that play in real time can nudge
+    /// it to keep their buffer level steady
+    fn sample_rate(&self) -> u32 {
+        DEFAULT_SAMPLE_RATE
+    }
+
+    /// Opts in to `queue_channel_samples`, read once when the emulation starts
+    fn wants_channel_samples(&self) -> bool {
+        false
+    }
+
+    /// Output of pulse 1, pulse 2, wave and noise before panning and master volume, one entry
+    /// per sample passed to `queue_samples`
+    fn queue_channel_samples(&mut self, _samples: &[[f32; 4]]) {}
+}
+
+/// Polled once before every frame
+pub trait InputSource {
+    fn poll(&mut self) -> InputState;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Hotkey {
+    SaveState(u8),
+    LoadState(u8),
+    /// Break into the debugger
+    Debug,
+}
+
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct InputState {
+    /// One `Button` bit each
+    pub buttons: u8,
+    /// Play the rewind history backwards instead of running the next frame
+    pub rewind: bool,
+    pub hotkey: Option<Hotkey>,
+    /// Stops the emulation, e.g. once the window was closed
+    pub quit: bool,
+}
+
+/// Drops all samples, for frontends without sound
+pub struct NullAudio;
+
+impl AudioSink for NullAudio {
+    fn queue_samples(&mut self, _samples: &[[f32; 2]]) {}
+}
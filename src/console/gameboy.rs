@@ -1,81 +1,712 @@
 use crate::console::bus::Bus;
+use crate::console::cartridge::header::CartridgeHeader;
+use crate::console::cartridge::load_error::LoadError;
+use crate::console::constants::{FRAME_DOT_CYCLES, SCREEN_HEIGHT, SCREEN_WIDTH};
+use crate::console::frontend::{AudioSink, Hotkey, InputSource, VideoSink};
 use crate::console::cpu::cpu::Cpu;
-use crate::console::gui::gui::{Gui, Palette};
+use crate::console::debugger::{Breakpoint, DebugBreak, Registers, RunTo, Watchpoint};
+use crate::console::gui::gpu::PixelLevel;
+use crate::console::gui::input::{input_states, Button};
+use crate::console::rewind::{Rewind, RewindConfig};
+use crate::console::save_state::{StateReader, StateWriter};
+use crate::console::serial::SerialDevice;
 use crate::console::timer::Timer;
 use crate::read_rom;
-use core::time::Duration;
-#[cfg(not(efi))]
-use std::time::Instant;
+use crate::save_ram;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 pub struct Gameboy {
     cpu: Cpu,
     bus: Bus,
     timer: Timer,
-    gui: Gui,
+    save_path: Option<String>,
+    rom_path: Option<String>,
+    // Dot cycles left until the CPU runs its next instruction
+    cpu_dot_cycles: u64,
+    rewind: Option<Rewind>,
+    frames: u64,
+    // One `Button` bit each
+    pressed_buttons: u8,
+    breakpoints: Vec<Breakpoint>,
+    run_to: Option<RunTo>,
+    // Last battery save between frames that failed to write, until the frontend takes it
+    save_error: Option<String>,
 }
 
+impl Default for Gameboy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Dirty battery RAM is flushed to disk about every 10 seconds
+const SAVE_FLUSH_INTERVAL_FRAMES: u64 = 600;
+
 impl Gameboy {
+    /// A machine without a cartridge, which reads open bus until a ROM is loaded
     pub fn new() -> Self {
-        let m_gui = Gui::new();
         Self {
             cpu: Cpu::new(),
             bus: Bus::new(),
             timer: Timer::new(),
-            gui: m_gui,
+            save_path: None,
+            rom_path: None,
+            cpu_dot_cycles: 0,
+            rewind: None,
+            frames: 0,
+            pressed_buttons: 0,
+            breakpoints: Vec::new(),
+            run_to: None,
+            save_error: None,
         }
     }
 
-    pub fn new_with_pal(z: u32, o: u32, t: u32, tr: u32) -> Self {
-        let palette = Palette::new(z, o, t, tr);
-        let m_gui = Gui::new_with_pal(palette);
-        Self {
-            cpu: Cpu::new(),
-            bus: Bus::new(),
-            timer: Timer::new(),
-            gui: m_gui,
+    pub fn from_rom(data: &[u8]) -> Result<Self, LoadError> {
+        let mut gameboy = Self::new();
+        gameboy.load_rom(data)?;
+        Ok(gameboy)
+    }
+
+    /// Registers a callback invoked whenever a rumble cartridge switches its motor on or off
+    pub fn set_rumble_callback<F: FnMut(bool) + 'static>(&mut self, callback: F) {
+        self.bus.set_rumble_callback(Box::new(callback));
+    }
+
+    /// Plugs a device into the link port, replacing the previous one. Nothing is connected by
+    /// default
+    pub fn set_serial_device<D: SerialDevice + 'static>(&mut self, device: D) {
+        self.bus.set_serial_device(Box::new(device));
+    }
+
+    /// Inserts a cartridge from ROM bytes, without battery saves or save state slots. Returns
+    /// the parsed header so the caller can report checksum mismatches
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<CartridgeHeader, LoadError> {
+        let header = self.bus.load_rom(data)?.clone();
+        self.rom_path = None;
+        self.save_path = None;
+        if let Some(rewind) = self.rewind.as_mut() {
+            rewind.clear();
+        }
+        Ok(header)
+    }
+
+    /// Loads a ROM file and its battery save, save state slots are kept next to the ROM.
+    /// Relative paths are resolved against the working directory
+    pub fn load(&mut self, cartridge_path: &str) -> Result<CartridgeHeader, LoadError> {
+        let data = read_rom::read_file(cartridge_path)?;
+        let header = self.load_rom(&data)?;
+        self.rom_path = Some(String::from(cartridge_path));
+
+        if self.bus.cartridge_mut().has_battery() {
+            let save_path = save_ram::save_path(cartridge_path);
+            if let Some(save) = save_ram::read_save(&save_path) {
+                self.bus
+                    .cartridge_mut()
+                    .load_battery(&save, save_ram::unix_time());
+            }
+            self.save_path = Some(save_path);
+        }
+
+        Ok(header)
+    }
+
+    /// Header of the inserted cartridge, `None` before a ROM is loaded
+    pub fn header(&self) -> Option<&CartridgeHeader> {
+        self.bus.cartridge().header()
+    }
+
+    /// Writes battery backed cartridge RAM to its `.sav` file. The RAM stays dirty if that fails,
+    /// so the next flush tries again
+    pub fn flush_save(&mut self) -> Result<(), String> {
+        let Some(save_path) = self.save_path.as_ref() else {
+            return Ok(());
+        };
+        let data = self.bus.cartridge().save_battery(save_ram::unix_time());
+        save_ram::write_save(save_path, &data)?;
+        self.bus.cartridge_mut().mark_saved();
+        Ok(())
+    }
+
+    /// Error of the last battery save flushed between frames, once
+    pub fn take_save_error(&mut self) -> Option<String> {
+        self.save_error.take()
+    }
+
+    /// Snapshot of the whole machine, tagged with the title and checksum of the loaded ROM
+    pub fn save_state(&self) -> Result<Vec<u8>, String> {
+        let Some(header) = self.bus.cartridge().header() else {
+            return Err(String::from("No ROM loaded"));
+        };
+
+        let mut state = StateWriter::new();
+        state.write_header(header);
+        self.cpu.save_state(&mut state);
+        state.write_u64(self.cpu_dot_cycles);
+        self.timer.save_state(&mut state);
+        self.bus.save_state(&mut state);
+        Ok(state.into_bytes())
+    }
+
+    /// Restores a snapshot taken with `save_state`. The machine is left untouched if the
+    /// state is invalid or belongs to another ROM
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), String> {
+        let Some(header) = self.bus.cartridge().header() else {
+            return Err(String::from("No ROM loaded"));
+        };
+        StateReader::new(data).read_header(header)?;
+
+        let backup = self.save_state()?;
+        let result = self.load_state_unchecked(data);
+        if let Err(e) = &result {
+            // A snapshot of this very machine always loads, but a broken one must not take the
+            // process down with it
+            self.load_state_unchecked(&backup).map_err(|restore_error| {
+                format!("{}, and restoring the previous state failed: {}", e, restore_error)
+            })?;
         }
+        result
     }
 
-    pub fn load(&mut self, cartridge_path: &str) {
-        let data = read_rom::read_file(cartridge_path);
-        self.bus.load_rom(&data);
+    fn load_state_unchecked(&mut self, data: &[u8]) -> Result<(), String> {
+        let header = self.bus.cartridge().header().unwrap().clone();
+        let mut state = StateReader::new(data);
+        state.read_header(&header)?;
+        self.cpu.load_state(&mut state)?;
+        self.cpu_dot_cycles = state.read_u64()?;
+        self.timer.load_state(&mut state)?;
+        self.bus.load_state(&mut state)?;
+        state.finish()
     }
 
-    pub fn run(&mut self) {
-        let mut dot_cycles_to_run_cpu = 0;
+    pub fn save_state_to_slot(&mut self, slot: u8) -> Result<(), String> {
+        let Some(rom_path) = self.rom_path.as_ref() else {
+            return Err(String::from("No ROM loaded"));
+        };
+        let state_path = save_ram::state_path(rom_path, slot);
+        save_ram::write_save(&state_path, &self.save_state()?)
+    }
 
-        const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
-        #[cfg(not(efi))]
-        let mut frame_start = Instant::now();
-        while !self.gui.should_close() {
-            // Cpu ticks every 4 dot cycles
-            if dot_cycles_to_run_cpu == 0 {
-                dot_cycles_to_run_cpu = (self.cpu.tick(&mut self.bus) as u64) * 4;
+    pub fn load_state_from_slot(&mut self, slot: u8) -> Result<(), String> {
+        let Some(rom_path) = self.rom_path.as_ref() else {
+            return Err(String::from("No ROM loaded"));
+        };
+        let state_path = save_ram::state_path(rom_path, slot);
+        let Some(data) = save_ram::read_save(&state_path) else {
+            return Err(format!("Slot {} is empty", slot));
+        };
+        self.load_state(&data)
+    }
+
+    pub(crate) fn tick_dot(&mut self) {
+        // Cpu ticks every 4 dot cycles
+        if self.cpu_dot_cycles == 0 {
+            self.bus.set_watching(true);
+            self.cpu_dot_cycles = (self.cpu.tick(&mut self.bus) as u64) * 4;
+            self.bus.set_watching(false);
+        }
+
+        self.timer.tick(&mut self.bus);
+        self.bus.tick();
+
+        self.cpu_dot_cycles -= 1;
+    }
+
+    /// Runs one CPU instruction, or what is left of the current one. Returns the dot cycles
+    /// that passed
+    pub fn step(&mut self) -> u64 {
+        let mut dot_cycles = 0;
+        loop {
+            self.tick_dot();
+            dot_cycles += 1;
+            if self.cpu_dot_cycles == 0 {
+                return dot_cycles;
             }
+        }
+    }
+
+    /// Runs until the start of the next vblank, or for a frame worth of dot cycles while the
+    /// LCD is off and there is no vblank. Takes rewind snapshots and flushes battery saves
+    /// between frames
+    pub fn run_frame(&mut self) {
+        for _ in 0..FRAME_DOT_CYCLES {
+            self.tick_dot();
+            if self.is_vblank_start() {
+                break;
+            }
+        }
 
-            self.timer.tick(&mut self.bus);
-            self.bus.tick();
-
-            dot_cycles_to_run_cpu -= 1;
-
-            cfg_if::cfg_if! {
-                if #[cfg(efi)] {
-                    if self.bus.is_vblank_start() {
-                        use uefi::prelude::*;
-                        self.gui.update(&mut self.bus);
-                        boot::stall(FRAME_DURATION.as_micros() as usize);
-                    }
-                } else {
-                    if self.bus.is_vblank_start() {
-                        self.gui.update(&mut self.bus);
-                        let elapsed = frame_start.elapsed();
-                        if elapsed < FRAME_DURATION {
-                            std::thread::sleep(FRAME_DURATION - elapsed);
-                        }
-                        frame_start = Instant::now();
-                    }
+        self.end_frame();
+    }
+
+    pub(crate) fn is_vblank_start(&self) -> bool {
+        self.bus.is_vblank_start()
+    }
+
+    // Bookkeeping between frames
+    pub(crate) fn end_frame(&mut self) {
+        self.frames += 1;
+        self.record_rewind();
+        self.flush_save_at_safe_point();
+    }
+
+    pub fn run_frames(&mut self, frames: u64) {
+        for _ in 0..frames {
+            self.run_frame();
+        }
+    }
+
+    pub fn run_cycles(&mut self, dot_cycles: u64) {
+        for _ in 0..dot_cycles {
+            self.tick_dot();
+        }
+    }
+
+    /// Stops `run_frame_debug` before these instructions
+    pub fn set_breakpoints(&mut self, breakpoints: &[Breakpoint]) {
+        self.breakpoints = breakpoints.to_vec();
+    }
+
+    /// Stops `run_frame_debug` and `debug_step` after instructions touching these addresses
+    pub fn set_watchpoints(&mut self, watchpoints: &[Watchpoint]) {
+        self.bus.set_watchpoints(watchpoints);
+    }
+
+    /// One-off stop for `run_frame_debug`, cleared once reached
+    pub fn set_run_to(&mut self, run_to: Option<RunTo>) {
+        self.run_to = run_to;
+    }
+
+    pub fn registers(&self) -> Registers {
+        self.cpu.registers()
+    }
+
+    /// Overwrites the CPU registers between instructions, the lower nibble of F always reads 0
+    pub fn set_registers(&mut self, registers: Registers) {
+        self.cpu.set_registers(registers);
+    }
+
+    /// ROM bank mapped at 0x4000-0x7FFF
+    pub fn rom_bank(&self) -> u16 {
+        self.bus.cartridge().rom_bank() as u16
+    }
+
+    /// Text of the instruction at `addr` and its size in bytes
+    pub fn disassemble(&self, addr: u16) -> (String, u16) {
+        Cpu::disassemble(&self.bus, addr)
+    }
+
+    // Checked between instructions, a halted CPU is not at any instruction
+    fn debug_stop(&mut self) -> Option<DebugBreak> {
+        let registers = self.cpu.registers();
+        if registers.halted {
+            return None;
+        }
+        let rom_bank = self.rom_bank();
+        if let Some(&breakpoint) = self
+            .breakpoints
+            .iter()
+            .find(|breakpoint| breakpoint.matches(registers.pc, rom_bank))
+        {
+            return Some(DebugBreak::Breakpoint(breakpoint));
+        }
+        if self
+            .run_to
+            .is_some_and(|run_to| run_to.reached(registers.pc, registers.sp))
+        {
+            self.run_to = None;
+            return Some(DebugBreak::RunTo);
+        }
+        None
+    }
+
+    /// Runs the rest of the current instruction and the next one, waiting out HALT for up to a
+    /// frame. Breakpoints are ignored so this also moves past the one that stopped the CPU
+    pub fn debug_step(&mut self) -> Option<DebugBreak> {
+        let mut hit = None;
+        for _ in 0..FRAME_DOT_CYCLES {
+            self.tick_dot();
+            hit = hit.or(self.bus.take_watch_hit());
+            if self.is_vblank_start() {
+                self.end_frame();
+            }
+            if self.cpu_dot_cycles == 0 && !self.cpu.registers().halted {
+                break;
+            }
+        }
+        hit.map(DebugBreak::Watchpoint)
+    }
+
+    /// `run_frame` that stops before an instruction at a breakpoint, after one that hit a
+    /// watchpoint or once the `set_run_to` target is reached. Calling it again after such a stop
+    /// carries on with the same frame
+    pub fn run_frame_debug(&mut self) -> Option<DebugBreak> {
+        let mut hit = None;
+        for _ in 0..FRAME_DOT_CYCLES {
+            if self.cpu_dot_cycles == 0 {
+                if let Some(hit) = hit {
+                    return Some(DebugBreak::Watchpoint(hit));
+                }
+                if let Some(stop) = self.debug_stop() {
+                    return Some(stop);
                 }
             }
+            self.tick_dot();
+            hit = hit.or(self.bus.take_watch_hit());
+            if self.is_vblank_start() {
+                break;
+            }
         }
+
+        self.end_frame();
+        hit.map(DebugBreak::Watchpoint)
+    }
+
+    /// Runs frame after frame until the input source asks to quit, then flushes the battery save
+    pub fn run(
+        &mut self,
+        video: &mut dyn VideoSink,
+        audio: &mut dyn AudioSink,
+        input: &mut dyn InputSource,
+    ) {
+        self.set_capture_channels(audio.wants_channel_samples());
+        loop {
+            let state = input.poll();
+            if state.quit {
+                break;
+            }
+            self.set_sample_rate(audio.sample_rate());
+            self.set_buttons(state.buttons);
+            if let Some(hotkey) = state.hotkey
+                && let Err(e) = self.handle_hotkey(hotkey)
+            {
+                video.show_message(&e);
+            }
+
+            if !(state.rewind && self.rewind_frame()) {
+                self.run_frame();
+            }
+            if let Some(e) = self.take_save_error() {
+                video.show_message(&e);
+            }
+
+            audio.queue_samples(self.audio_samples());
+            audio.queue_channel_samples(self.channel_samples());
+            self.clear_audio_samples();
+            video.present(self.framebuffer());
+        }
+
+        if let Err(e) = self.flush_save() {
+            video.show_message(&e);
+        }
+    }
+
+    fn handle_hotkey(&mut self, hotkey: Hotkey) -> Result<(), String> {
+        match hotkey {
+            Hotkey::SaveState(slot) => self.save_state_to_slot(slot),
+            Hotkey::LoadState(slot) => self.load_state_from_slot(slot),
+            // Left to frontends that have a debugger
+            Hotkey::Debug => Ok(()),
+        }
+    }
+
+    /// Shades of the last frame, row by row
+    pub fn framebuffer(&self) -> &[PixelLevel; SCREEN_WIDTH * SCREEN_HEIGHT] {
+        self.bus.get_gpu_buffer()
+    }
+
+    /// Output rate of `audio_samples`, 48 kHz by default. Can be changed between frames without
+    /// a gap in the sound
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.bus.audio_mut().set_sample_rate(sample_rate);
+    }
+
+    /// Stereo samples mixed since the last `clear_audio_samples`, as (left, right) pairs in
+    /// -1.0..=1.0. At most a second is kept when they are not collected
+    pub fn audio_samples(&self) -> &[[f32; 2]] {
+        self.bus.audio().samples()
+    }
+
+    /// Records the output of every channel next to the mix, for `channel_samples`
+    pub fn set_capture_channels(&mut self, capture_channels: bool) {
+        self.bus.audio_mut().set_capture_channels(capture_channels);
+    }
+
+    /// Pulse 1, pulse 2, wave and noise before panning and master volume, one entry per
+    /// sample of `audio_samples`. Empty unless enabled with `set_capture_channels`
+    pub fn channel_samples(&self) -> &[[f32; 4]] {
+        self.bus.audio().channel_samples()
+    }
+
+    pub fn clear_audio_samples(&mut self) {
+        self.bus.audio_mut().clear_samples();
+    }
+
+    pub fn set_button(&mut self, button: Button, pressed: bool) {
+        if pressed {
+            self.set_buttons(self.pressed_buttons | button as u8);
+        } else {
+            self.set_buttons(self.pressed_buttons & !(button as u8));
+        }
+    }
+
+    /// Replaces the state of all buttons at once, one `Button` bit each
+    pub fn set_buttons(&mut self, pressed: u8) {
+        self.pressed_buttons = pressed;
+        let (dpad, buttons) = input_states(pressed);
+        self.bus.update_input_state(dpad, buttons);
+    }
+
+    /// Reads a byte the way the CPU would
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.bus.read_from_8b(addr)
+    }
+
+    /// Writes a byte the way the CPU would, ROM writes go to the banking registers
+    pub fn poke(&mut self, addr: u16, value: u8) {
+        self.bus.write_to_8b(addr, value);
+    }
+
+    /// The whole address space as the CPU currently sees it
+    pub fn memory_dump(&self) -> Vec<u8> {
+        (0..=u16::MAX).map(|addr| self.bus.read_from_8b(addr)).collect()
+    }
+
+    pub fn enable_rewind(&mut self, config: RewindConfig) {
+        self.rewind = Some(Rewind::new(config));
+    }
+
+    fn record_rewind(&mut self) {
+        let frames = self.frames;
+        if !self.rewind.as_ref().is_some_and(|rewind| rewind.should_snapshot(frames)) {
+            return;
+        }
+        if let Ok(state) = self.save_state() {
+            self.rewind.as_mut().unwrap().push(state);
+        }
+    }
+
+    /// Plays the rewind history backwards by one frame, restoring an older snapshot every
+    /// `interval_frames` frames and staying on the oldest one once it runs out. Returns false if
+    /// rewinding is disabled or nothing was recorded yet
+    pub fn rewind_frame(&mut self) -> bool {
+        let Some(rewind) = self.rewind.as_mut() else {
+            return false;
+        };
+        if !rewind.next_playback_frame() {
+            // The snapshot restored last is still on screen
+            return rewind.len() > 0;
+        }
+        let Some(state) = rewind.pop() else {
+            return false;
+        };
+        let loaded = self.load_state(&state).is_ok();
+        // Keep the oldest snapshot around so rewinding can continue from it later
+        if self.rewind.as_ref().is_some_and(|rewind| rewind.len() == 0) {
+            self.rewind.as_mut().unwrap().push(state);
+        }
+        loaded
+    }
+
+    // Called between frames, flushes right after the game finished saving and periodically
+    // as a fallback for games that never disable RAM
+    fn flush_save_at_safe_point(&mut self) {
+        let periodic = self.frames.is_multiple_of(SAVE_FLUSH_INTERVAL_FRAMES);
+        let cartridge = self.bus.cartridge_mut();
+        let periodic = periodic && cartridge.is_ram_dirty();
+        if (cartridge.take_save_pending() || periodic)
+            && let Err(e) = self.flush_save()
+        {
+            self.save_error = Some(e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::constants::*;
+    use crate::console::frontend::*;
+    use crate::console::gameboy::*;
+    use crate::console::gui::input::P1Flags;
+
+    fn test_rom() -> Vec<u8> {
+        let mut rom = vec![0u8; 2 * ROM_BANK_SIZE];
+        rom[TITLE_ADDR..TITLE_ADDR + 4].copy_from_slice(b"CORE");
+        rom
+    }
+
+    #[test]
+    fn test_from_rom() {
+        let gameboy = Gameboy::from_rom(&test_rom()).unwrap();
+        assert_eq!(gameboy.header().unwrap().title, "CORE");
+        assert!(Gameboy::from_rom(&[0u8; 0x100]).is_err());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert_eq!(
+            Gameboy::from_rom(&[0u8; 0x100]).err(),
+            Some(LoadError::TruncatedHeader { len: 0x100 })
+        );
+
+        let mut rom = test_rom();
+        rom[CARTRIDGE_TYPE_ADDR] = 0xFC;
+        assert!(matches!(
+            Gameboy::from_rom(&rom),
+            Err(LoadError::UnsupportedMapper(cartridge_type)) if cartridge_type.code == 0xFC
+        ));
+
+        let path = "/nonexistent/rustemu/missing.gb";
+        assert_eq!(
+            Gameboy::new().load(path).err(),
+            Some(LoadError::NotFound {
+                path: String::from(path)
+            })
+        );
+    }
+
+    #[test]
+    fn test_peek_poke() {
+        let mut gameboy = Gameboy::from_rom(&test_rom()).unwrap();
+        gameboy.poke(0xC000, 0x42);
+        assert_eq!(gameboy.peek(0xC000), 0x42);
+        assert_eq!(gameboy.memory_dump()[0xC000], 0x42);
+    }
+
+    #[test]
+    fn test_failed_save_stays_dirty() {
+        let mut rom = test_rom();
+        // MBC1 with battery backed 8 KiB RAM
+        rom[CARTRIDGE_TYPE_ADDR] = 0x03;
+        rom[RAM_SIZE_ADDR] = 0x02;
+        let mut gameboy = Gameboy::from_rom(&rom).unwrap();
+        gameboy.save_path = Some(String::from("/nonexistent/rustemu/missing.sav"));
+
+        gameboy.poke(0x0000, 0x0A);
+        gameboy.poke(0xA000, 0x42);
+        assert!(gameboy.bus.cartridge().is_ram_dirty());
+        assert!(gameboy.flush_save().is_err());
+        assert!(gameboy.bus.cartridge().is_ram_dirty());
+    }
+
+    #[test]
+    fn test_state_errors() {
+        let mut gameboy = Gameboy::from_rom(&test_rom()).unwrap();
+        gameboy.poke(0xC000, 0x42);
+        let state = gameboy.save_state().unwrap();
+
+        gameboy.poke(0xC000, 0x24);
+        assert!(gameboy.load_state(&state[..state.len() - 1]).is_err());
+        assert_eq!(gameboy.peek(0xC000), 0x24, "a truncated state should change nothing");
+        assert!(gameboy.load_state(&state).is_ok());
+        assert_eq!(gameboy.peek(0xC000), 0x42);
+
+        gameboy.rom_path = Some(String::from("/nonexistent/rustemu/missing.gb"));
+        assert!(gameboy.save_state_to_slot(1).is_err());
+    }
+
+    #[test]
+    fn test_step_and_frame() {
+        let mut gameboy = Gameboy::from_rom(&test_rom()).unwrap();
+        let dot_cycles = gameboy.step();
+        assert!(dot_cycles > 0 && dot_cycles.is_multiple_of(4));
+
+        gameboy.run_frame();
+        assert_eq!(gameboy.frames, 1);
+    }
+
+    #[test]
+    fn test_debug_stops() {
+        use crate::console::debugger::{WatchHit, WatchKind};
+
+        // The boot ROM starts by clearing VRAM from 0x9FFF down
+        let mut gameboy = Gameboy::from_rom(&test_rom()).unwrap();
+        let breakpoint = Breakpoint::new(0x0004);
+        gameboy.set_breakpoints(&[breakpoint]);
+        assert_eq!(gameboy.run_frame_debug(), Some(DebugBreak::Breakpoint(breakpoint)));
+        assert_eq!(gameboy.run_frame_debug(), Some(DebugBreak::Breakpoint(breakpoint)));
+        assert_eq!(gameboy.registers().pc, 0x0004);
+        assert_eq!(gameboy.disassemble(0x0004), (String::from("LD HL, $9FFF"), 3));
+
+        gameboy.set_watchpoints(&[Watchpoint {
+            kind: WatchKind::Write,
+            start: 0x9FFF,
+            end: 0x9FFF,
+        }]);
+        assert_eq!(gameboy.debug_step(), None);
+        assert_eq!(gameboy.registers().pc, 0x0007);
+        let hit = WatchHit {
+            write: true,
+            addr: 0x9FFF,
+            value: 0,
+        };
+        assert_eq!(gameboy.run_frame_debug(), Some(DebugBreak::Watchpoint(hit)));
+        assert_eq!(gameboy.registers().pc, 0x0008);
+        assert_eq!(gameboy.frames, 0);
+    }
+
+    #[test]
+    fn test_set_button() {
+        let mut gameboy = Gameboy::from_rom(&test_rom()).unwrap();
+        gameboy.set_button(Button::Start, true);
+        gameboy.set_button(Button::Down, true);
+
+        // Select the action buttons, P1 is active low
+        gameboy.poke(0xFF00, P1Flags::DPAD as u8);
+        assert_eq!(gameboy.peek(0xFF00) & 0x0F, 0b0111);
+        gameboy.poke(0xFF00, P1Flags::BUTTONS as u8);
+        assert_eq!(gameboy.peek(0xFF00) & 0x0F, 0b0111);
+
+        gameboy.set_button(Button::Down, false);
+        assert_eq!(gameboy.peek(0xFF00) & 0x0F, 0b1111);
+    }
+
+    struct TestVideo {
+        frames: u64,
+        messages: Vec<String>,
+    }
+
+    impl VideoSink for TestVideo {
+        fn present(&mut self, _frame: &[PixelLevel; SCREEN_WIDTH * SCREEN_HEIGHT]) {
+            self.frames += 1;
+        }
+
+        fn show_message(&mut self, message: &str) {
+            self.messages.push(String::from(message));
+        }
+    }
+
+    struct TestInput {
+        polls: u64,
+    }
+
+    impl InputSource for TestInput {
+        fn poll(&mut self) -> InputState {
+            self.polls += 1;
+            InputState {
+                buttons: Button::A as u8,
+                hotkey: (self.polls == 1).then_some(Hotkey::SaveState(1)),
+                quit: self.polls > 3,
+                ..InputState::default()
+            }
+        }
+    }
+
+    #[test]
+    fn test_run_drives_frontend() {
+        let mut gameboy = Gameboy::from_rom(&test_rom()).unwrap();
+        let mut video = TestVideo {
+            frames: 0,
+            messages: Vec::new(),
+        };
+        gameboy.run(&mut video, &mut NullAudio, &mut TestInput { polls: 0 });
+
+        assert_eq!(video.frames, 3);
+        assert_eq!(gameboy.frames, 3);
+        assert_eq!(gameboy.pressed_buttons, Button::A as u8);
+        // Slots need a ROM path, a ROM loaded from bytes has none
+        assert_eq!(video.messages, ["No ROM loaded"]);
     }
 }
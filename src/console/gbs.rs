@@ -0,0 +1,361 @@
+use crate::console::bus::Bus;
+use crate::console::cartridge::header::header_string;
+use crate::console::constants::{BOOT_ROM_DISABLE_ADDR, FRAME_DOT_CYCLES, ROM_BANK_SIZE};
+use crate::console::cpu::cpu::Cpu;
+use crate::console::interrupt::Interrupt;
+use crate::console::timer::Timer;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+const GBS_HEADER_SIZE: usize = 0x70;
+const GBS_MAGIC: &[u8; 3] = b"GBS";
+// Space below the load address holds the RST and interrupt vectors, the cartridge header
+// and the driver
+const MIN_LOAD_ADDRESS: u16 = 0x0400;
+const MAX_LOAD_ADDRESS: u16 = 0x7FFF;
+const DRIVER_ADDR: u16 = 0x0150;
+const VBLANK_VECTOR: usize = 0x40;
+const TIMER_VECTOR: usize = 0x50;
+// TAC bit 2 plays at the timer rate instead of at vblank
+const TAC_TIMER_ENABLE: u8 = 0x04;
+// MBC5 with RAM, so the banks switch through 0x2000 and A000-BFFF is usable work RAM
+const IMAGE_CARTRIDGE_TYPE: u8 = 0x1A;
+const IMAGE_RAM_SIZE_CODE: u8 = 0x02;
+
+/// Header of a Game Boy Sound System rip
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GbsHeader {
+    pub song_count: u8,
+    /// 1-based, like the track numbers `GbsPlayer::start_track` takes. Clamped to the songs in
+    /// the file, so a rip with a broken value still plays
+    pub first_song: u8,
+    pub load_address: u16,
+    pub init_address: u16,
+    pub play_address: u16,
+    pub stack_pointer: u16,
+    pub timer_modulo: u8,
+    pub timer_control: u8,
+    pub title: String,
+    pub author: String,
+    pub copyright: String,
+}
+
+impl GbsHeader {
+    pub fn parse(data: &[u8]) -> Result<Self, String> {
+        if data.len() < GBS_HEADER_SIZE {
+            return Err(format!(
+                "GBS file is {} bytes, too short for its header",
+                data.len()
+            ));
+        }
+        if &data[0..3] != GBS_MAGIC {
+            return Err(String::from("Not a GBS file"));
+        }
+        if data[3] != 1 {
+            return Err(format!("Unsupported GBS version {}", data[3]));
+        }
+
+        let word = |offset: usize| u16::from_le_bytes([data[offset], data[offset + 1]]);
+        let header = Self {
+            song_count: data[0x04],
+            first_song: data[0x05].clamp(1, data[0x04].max(1)),
+            load_address: word(0x06),
+            init_address: word(0x08),
+            play_address: word(0x0A),
+            stack_pointer: word(0x0C),
+            timer_modulo: data[0x0E],
+            timer_control: data[0x0F],
+            title: header_string(&data[0x10..0x30]),
+            author: header_string(&data[0x30..0x50]),
+            copyright: header_string(&data[0x50..0x70]),
+        };
+
+        if header.song_count == 0 {
+            return Err(String::from("GBS file has no songs"));
+        }
+        if !(MIN_LOAD_ADDRESS..=MAX_LOAD_ADDRESS).contains(&header.load_address) {
+            return Err(format!(
+                "GBS load address ${:04X} is outside ${:04X}-${:04X}",
+                header.load_address, MIN_LOAD_ADDRESS, MAX_LOAD_ADDRESS
+            ));
+        }
+        Ok(header)
+    }
+
+    /// PLAY is called from the timer interrupt instead of at every vblank
+    pub fn uses_timer(&self) -> bool {
+        self.timer_control & TAC_TIMER_ENABLE != 0
+    }
+}
+
+impl fmt::Display for GbsHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Title:           {}", self.title)?;
+        writeln!(f, "Author:          {}", self.author)?;
+        writeln!(f, "Copyright:       {}", self.copyright)?;
+        writeln!(
+            f,
+            "Songs:           {} (first {})",
+            self.song_count, self.first_song
+        )?;
+        if self.uses_timer() {
+            write!(
+                f,
+                "Play rate:       timer (TAC ${:02X}, TMA ${:02X})",
+                self.timer_control, self.timer_modulo
+            )
+        } else {
+            write!(f, "Play rate:       vblank")
+        }
+    }
+}
+
+/// Cartridge image with the GBS data at its load address and a driver that calls INIT for the
+/// 0-based `track`, then idles while the interrupt vectors call PLAY
+fn build_image(header: &GbsHeader, data: &[u8], track: u8) -> Vec<u8> {
+    let data = &data[GBS_HEADER_SIZE..];
+    let load = header.load_address as usize;
+    let size = (load + data.len())
+        .next_power_of_two()
+        .max(2 * ROM_BANK_SIZE);
+    let mut image = vec![0xFF; size];
+    image[load..load + data.len()].copy_from_slice(data);
+
+    // RST vectors are relocated to the load address
+    for vector in (0x00..0x40).step_by(8) {
+        let [low, high] = ((load + vector) as u16).to_le_bytes();
+        image[vector..vector + 3].copy_from_slice(&[0xC3, low, high]);
+    }
+    // Unused interrupts return right away, vblank and timer call PLAY
+    for vector in (0x40..=0x60).step_by(8) {
+        image[vector] = 0xD9;
+    }
+    let [play_low, play_high] = header.play_address.to_le_bytes();
+    for vector in [VBLANK_VECTOR, TIMER_VECTOR] {
+        image[vector..vector + 4].copy_from_slice(&[0xCD, play_low, play_high, 0xD9]);
+    }
+
+    image[0x147] = IMAGE_CARTRIDGE_TYPE;
+    image[0x148] = (size / (2 * ROM_BANK_SIZE)).trailing_zeros() as u8;
+    image[0x149] = IMAGE_RAM_SIZE_CODE;
+
+    let interrupt = if header.uses_timer() {
+        Interrupt::Timer
+    } else {
+        Interrupt::VBlank
+    };
+    let [sp_low, sp_high] = header.stack_pointer.to_le_bytes();
+    let [init_low, init_high] = header.init_address.to_le_bytes();
+    #[rustfmt::skip]
+    let driver = [
+        0xF3,                                   // DI
+        0x31, sp_low, sp_high,                  // LD SP, stack pointer
+        0x3E, 0x0A, 0xEA, 0x00, 0x00,           // Enable cartridge RAM
+        0x3E, 0x80, 0xE0, 0x26,                 // NR52: sound on
+        0x3E, 0xFF, 0xE0, 0x25,                 // NR51: every channel on both sides
+        0x3E, 0x77, 0xE0, 0x24,                 // NR50: full volume
+        0x3E, header.timer_modulo, 0xE0, 0x06,  // TMA
+        0x3E, header.timer_control, 0xE0, 0x07, // TAC
+        0xAF, 0xE0, 0x0F,                       // Clear IF
+        0x3E, interrupt as u8, 0xE0, 0xFF,      // IE
+        0x3E, track,                            // LD A, track
+        0xCD, init_low, init_high,              // CALL INIT
+        0xFB,                                   // EI
+        0x76,                                   // HALT
+        0x18, 0xFD,                             // JR back to HALT
+    ];
+    let driver_addr = DRIVER_ADDR as usize;
+    image[driver_addr..driver_addr + driver.len()].copy_from_slice(&driver);
+    image
+}
+
+/// Plays GBS rips on the CPU, timer and APU of a `Gameboy`, without the PPU
+pub struct GbsPlayer {
+    header: GbsHeader,
+    data: Vec<u8>,
+    cpu: Cpu,
+    bus: Bus,
+    timer: Timer,
+    // Dot cycles left until the CPU runs its next instruction
+    cpu_dot_cycles: u64,
+    // Dot cycles since the last vblank interrupt
+    frame_dot_cycles: u64,
+    sample_rate: Option<u32>,
+    capture_channels: bool,
+}
+
+impl GbsPlayer {
+    /// Parses a GBS file and starts its first song
+    pub fn new(data: &[u8]) -> Result<Self, String> {
+        let header = GbsHeader::parse(data)?;
+        let mut player = Self {
+            header,
+            data: data.to_vec(),
+            cpu: Cpu::new(),
+            bus: Bus::new(),
+            timer: Timer::new(),
+            cpu_dot_cycles: 0,
+            frame_dot_cycles: 0,
+            sample_rate: None,
+            capture_channels: false,
+        };
+        player.start_track(player.header.first_song)?;
+        Ok(player)
+    }
+
+    pub fn header(&self) -> &GbsHeader {
+        &self.header
+    }
+
+    /// Resets the machine and calls INIT for `track`, counted from 1
+    pub fn start_track(&mut self, track: u8) -> Result<(), String> {
+        if track == 0 || track > self.header.song_count {
+            return Err(format!(
+                "Track {} does not exist, the file has {}",
+                track, self.header.song_count
+            ));
+        }
+        let image = build_image(&self.header, &self.data, track - 1);
+        self.cpu = Cpu::new();
+        self.bus = Bus::new();
+        self.timer = Timer::new();
+        self.cpu_dot_cycles = 0;
+        self.frame_dot_cycles = 0;
+        self.bus
+            .load_rom(&image)
+            .map_err(|e| format!("Failed to map the GBS data: {}", e))?;
+        self.bus.set_gpu_enabled(false);
+        self.bus.write_to_8b(BOOT_ROM_DISABLE_ADDR, 1);
+        if let Some(sample_rate) = self.sample_rate {
+            self.bus.audio_mut().set_sample_rate(sample_rate);
+        }
+        self.bus
+            .audio_mut()
+            .set_capture_channels(self.capture_channels);
+        self.cpu.set_pc(DRIVER_ADDR);
+        Ok(())
+    }
+
+    fn tick_dot(&mut self) {
+        if self.cpu_dot_cycles == 0 {
+            self.cpu_dot_cycles = (self.cpu.tick(&mut self.bus) as u64) * 4;
+        }
+
+        self.timer.tick(&mut self.bus);
+        self.bus.tick();
+
+        // Stands in for the PPU, which would raise it at the start of every vblank
+        self.frame_dot_cycles += 1;
+        if self.frame_dot_cycles == FRAME_DOT_CYCLES {
+            self.frame_dot_cycles = 0;
+            self.bus.request_interrupt(Interrupt::VBlank);
+        }
+
+        self.cpu_dot_cycles -= 1;
+    }
+
+    /// Runs for the length of a frame, about 1/60 of a second
+    pub fn run_frame(&mut self) {
+        for _ in 0..FRAME_DOT_CYCLES {
+            self.tick_dot();
+        }
+    }
+
+    /// Output rate of `audio_samples`, 48 kHz by default
+    pub fn set_sample_rate(&mut self, sample_rate: u32) {
+        self.sample_rate = Some(sample_rate);
+        self.bus.audio_mut().set_sample_rate(sample_rate);
+    }
+
+    /// Stereo samples mixed since the last `clear_audio_samples`, like `Gameboy::audio_samples`
+    pub fn audio_samples(&self) -> &[[f32; 2]] {
+        self.bus.audio().samples()
+    }
+
+    /// Records the output of every channel next to the mix, for `channel_samples`
+    pub fn set_capture_channels(&mut self, capture_channels: bool) {
+        self.capture_channels = capture_channels;
+        self.bus.audio_mut().set_capture_channels(capture_channels);
+    }
+
+    pub fn channel_samples(&self) -> &[[f32; 4]] {
+        self.bus.audio().channel_samples()
+    }
+
+    pub fn clear_audio_samples(&mut self) {
+        self.bus.audio_mut().clear_samples();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::gbs::*;
+
+    // INIT stores the track in C001, PLAY counts its calls in C000
+    fn test_gbs(timer_control: u8) -> Vec<u8> {
+        let mut data = vec![0; GBS_HEADER_SIZE];
+        data[0..4].copy_from_slice(b"GBS\x01");
+        data[0x04] = 3;
+        data[0x05] = 2;
+        data[0x06..0x08].copy_from_slice(&0x0400u16.to_le_bytes());
+        data[0x08..0x0A].copy_from_slice(&0x0400u16.to_le_bytes());
+        data[0x0A..0x0C].copy_from_slice(&0x0404u16.to_le_bytes());
+        data[0x0C..0x0E].copy_from_slice(&0xDFFEu16.to_le_bytes());
+        data[0x0F] = timer_control;
+        data[0x10..0x14].copy_from_slice(b"Test");
+        data.extend([0xEA, 0x01, 0xC0, 0xC9, 0x21, 0x00, 0xC0, 0x34, 0xC9]);
+        data
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let header = GbsHeader::parse(&test_gbs(0)).unwrap();
+        assert_eq!(header.song_count, 3);
+        assert_eq!(header.first_song, 2);
+        assert_eq!(header.play_address, 0x0404);
+        assert_eq!(header.title, "Test");
+        assert!(!header.uses_timer());
+
+        let mut data = test_gbs(0);
+        data[0x05] = 9;
+        assert_eq!(GbsHeader::parse(&data).unwrap().first_song, 3);
+        assert!(GbsPlayer::new(&data).is_ok());
+
+        assert!(GbsHeader::parse(&test_gbs(0)[..0x40]).is_err());
+        let mut data = test_gbs(0);
+        data[0x06..0x08].copy_from_slice(&0x0100u16.to_le_bytes());
+        assert!(GbsHeader::parse(&data).is_err());
+        data[0] = b'X';
+        assert!(GbsHeader::parse(&data).is_err());
+    }
+
+    #[test]
+    fn test_play_at_vblank() {
+        let mut player = GbsPlayer::new(&test_gbs(0)).unwrap();
+        // INIT gets the 0-based track
+        player.run_frame();
+        assert_eq!(player.bus.read_from_8b(0xC001), 1);
+
+        player.start_track(3).unwrap();
+        for _ in 0..60 {
+            player.run_frame();
+        }
+        assert_eq!(player.bus.read_from_8b(0xC001), 2);
+        // The last vblank is raised on the final dot and still pending
+        assert!((59..=60).contains(&player.bus.read_from_8b(0xC000)));
+        assert!(player.start_track(4).is_err());
+    }
+
+    #[test]
+    fn test_play_at_timer_rate() {
+        // 4096 Hz timer overflowing every 256 steps
+        let mut player = GbsPlayer::new(&test_gbs(0x04)).unwrap();
+        for _ in 0..60 {
+            player.run_frame();
+        }
+        assert_eq!(player.bus.read_from_8b(0xC000), 16);
+    }
+}
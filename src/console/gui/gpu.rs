@@ -2,6 +2,8 @@ use crate::console::constants::*;
 use crate::console::hw_register::HwRegister::{OBP0, OBP1};
 use crate::console::hw_register::{HwRegister, HwRegisters};
 use crate::console::interrupt::Interrupt;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
 
 #[repr(u8)]
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
@@ -591,4 +593,45 @@ impl Gpu {
     pub fn is_vblank_started(&self) -> bool {
         self.start_vblank
     }
+
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u64(self.dots);
+        state.write_u8(self.gpu_mode as u8);
+        state.write_bytes(&self.vram);
+        // Line buffers are filled at the start of a scanline and read until its end
+        for line in [&self.buffer[..], &self.bg_line, &self.wd_line, &self.oam_line] {
+            for &pixel in line {
+                state.write_u8(pixel as u8);
+            }
+        }
+        for flags in [&self.oam_prio, &self.oam_palette] {
+            for &flag in flags {
+                state.write_bool(flag);
+            }
+        }
+        state.write_bool(self.start_vblank);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.dots = state.read_u64()?;
+        self.gpu_mode = match state.read_u8()? {
+            0b00 => GpuMode::HBlank,
+            0b01 => GpuMode::VBlank,
+            0b10 => GpuMode::OamScan,
+            _ => GpuMode::Drawing,
+        };
+        state.read_into(&mut self.vram)?;
+        for line in [&mut self.buffer[..], &mut self.bg_line, &mut self.wd_line, &mut self.oam_line] {
+            for pixel in line.iter_mut() {
+                *pixel = PixelLevel::from(state.read_u8()? & 0b11);
+            }
+        }
+        for flags in [&mut self.oam_prio, &mut self.oam_palette] {
+            for flag in flags.iter_mut() {
+                *flag = state.read_bool()?;
+            }
+        }
+        self.start_vblank = state.read_bool()?;
+        Ok(())
+    }
 }
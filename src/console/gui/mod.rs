@@ -1,3 +1,2 @@
 pub mod gpu;
-pub mod gui;
 pub mod input;
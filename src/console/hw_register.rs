@@ -4,6 +4,8 @@ use crate::console::gui::gpu::{GpuMode, STATFlag};
 use crate::console::gui::input::P1_WRITE_MASK;
 use crate::console::hw_register::HwRegister::{DIV, IE, IF, LY, LYC, P1, STAT, TIMA};
 use crate::console::interrupt::Interrupt;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
 
 const INNER_REG_ARR_SIZE: usize = 0x100;
 const INNER_REG_IDX_FLAG: usize = 0x00FF;
@@ -12,34 +14,11 @@ const INNER_REG_IDX_FLAG: usize = 0x00FF;
 #[derive(Copy, Clone)]
 pub enum HwRegister {
     P1 = 0xff00,
-    SB = 0xff01,
-    SC = 0xff02,
     DIV = 0xff04,
     TIMA = 0xff05,
     TMA = 0xff06,
     TAC = 0xff07,
     IF = 0xff0f,
-    NR10 = 0xff10,
-    NR11 = 0xff11,
-    NR12 = 0xff12,
-    NR13 = 0xff13,
-    NR14 = 0xff14,
-    NR21 = 0xff16,
-    NR22 = 0xff17,
-    NR23 = 0xff18,
-    NR24 = 0xff19,
-    NR30 = 0xff1a,
-    NR31 = 0xff1b,
-    NR32 = 0xff1c,
-    NR33 = 0xff1d,
-    NR34 = 0xff1e,
-    NR41 = 0xff20,
-    NR42 = 0xff21,
-    NR43 = 0xff22,
-    NR44 = 0xff23,
-    NR50 = 0xff24,
-    NR51 = 0xff25,
-    NR52 = 0xff26,
     LCDC = 0xff40,
     STAT = 0xff41,
     SCY = 0xff42,
@@ -65,9 +44,8 @@ impl HwRegister {
     #[inline]
     pub fn supported_addr(addr: u16) -> bool {
         matches!(addr,
-        0xff00..=0xff02 | 0xff04..=0xff07 | 0xff0f |
-        0xff10..=0xff14 | 0xff16..=0xff19 | 0xff1a..=0xff1e |
-        0xff20..=0xff26 | 0xff40..=0xff4b | 0xffff)
+        0xff00 | 0xff04..=0xff07 | 0xff0f |
+        0xff40..=0xff4b | 0xffff)
     }
 
     #[inline]
@@ -131,14 +109,6 @@ impl HwRegisters {
                 let p1 = self.reg_as_mut_ref(P1);
                 *p1 = (*p1 & !P1_WRITE_MASK) | (value & P1_WRITE_MASK);
             }
-            SC => {
-                let sc = if (value & 0x80) != 0 {
-                    value & 0x7F
-                } else {
-                    value
-                };
-                self.raw_write(SC, sc);
-            }
             DIV => self.raw_write(DIV, 0x00),
             TAC => {
                 self.inc_tima();
@@ -264,6 +234,20 @@ impl HwRegisters {
         self.handle_stat_line_mode_cond(STATFlag::Mode2IntSelect);
     }
 
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_bytes(&self.regs);
+        state.write_bool(self.prev_stat_line);
+        state.write_bool(self.stat_line);
+        self.dma_data.save_state(state);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        state.read_into(&mut self.regs)?;
+        self.prev_stat_line = state.read_bool()?;
+        self.stat_line = state.read_bool()?;
+        self.dma_data.load_state(state)
+    }
+
     pub fn update_input_state(&mut self, dpad_state: u8, button_state: u8) {
         self.prev_dpad_state = self.dpad_state;
         self.prev_button_state = self.button_state;
@@ -0,0 +1,195 @@
+use crate::console::constants::FRAME_DOT_CYCLES;
+use crate::console::gameboy::Gameboy;
+use crate::console::serial::{DOTS_PER_BIT, SerialDevice};
+use alloc::rc::Rc;
+use alloc::string::String;
+use core::cell::RefCell;
+
+// A byte takes 8 bit periods of the master's clock to reach the other side
+const TRANSFER_DOTS: u32 = 8 * DOTS_PER_BIT;
+
+/// State of the wire, indexed by side
+#[derive(Default)]
+struct Cable {
+    // SB of a side waiting on the external clock, as of its last dot
+    listening: [Option<u8>; 2],
+    // Byte being clocked into a side, with the dots left until all 8 bits are in
+    incoming: [Option<(u8, u32)>; 2],
+}
+
+/// One plug of a cable made by `cable`
+struct CableEnd {
+    cable: Rc<RefCell<Cable>>,
+    side: usize,
+}
+
+fn cable() -> [CableEnd; 2] {
+    let cable = Rc::new(RefCell::new(Cable::default()));
+    [0, 1].map(|side| CableEnd {
+        cable: cable.clone(),
+        side,
+    })
+}
+
+impl SerialDevice for CableEnd {
+    fn transfer(&mut self, byte: u8) -> Option<u8> {
+        let mut cable = self.cable.borrow_mut();
+        let other = 1 - self.side;
+        // Nobody on the other end reads the pulled up data line
+        let Some(answer) = cable.listening[other].take() else {
+            return Some(0xFF);
+        };
+        cable.incoming[other] = Some((byte, TRANSFER_DOTS));
+        Some(answer)
+    }
+
+    fn external_transfer(&mut self, byte: u8) -> Option<u8> {
+        let mut cable = self.cable.borrow_mut();
+        match cable.incoming[self.side] {
+            Some((incoming, 0)) => {
+                cable.incoming[self.side] = None;
+                Some(incoming)
+            }
+            Some(_) => None,
+            None => {
+                cable.listening[self.side] = Some(byte);
+                None
+            }
+        }
+    }
+
+    fn tick(&mut self) {
+        let mut cable = self.cable.borrow_mut();
+        cable.listening[self.side] = None;
+        // A byte that arrived after the Game Boy stopped waiting is lost
+        cable.incoming[self.side] = match cable.incoming[self.side] {
+            Some((_, 0)) | None => None,
+            Some((byte, dots)) => Some((byte, dots - 1)),
+        };
+    }
+}
+
+/// Two Game Boys with a link cable between them, run dot by dot so every transfer lands at the
+/// same point for both. Nothing outside the pair decides the timing, so every run is the same
+pub struct LinkedGameboys {
+    gameboys: [Gameboy; 2],
+}
+
+impl LinkedGameboys {
+    /// Plugs the cable into both link ports, replacing what was connected before
+    pub fn new(mut first: Gameboy, mut second: Gameboy) -> Self {
+        let [first_end, second_end] = cable();
+        first.set_serial_device(first_end);
+        second.set_serial_device(second_end);
+        Self {
+            gameboys: [first, second],
+        }
+    }
+
+    pub fn gameboys(&self) -> &[Gameboy; 2] {
+        &self.gameboys
+    }
+
+    pub fn gameboys_mut(&mut self) -> &mut [Gameboy; 2] {
+        &mut self.gameboys
+    }
+
+    fn tick_dot(&mut self) {
+        for gameboy in &mut self.gameboys {
+            gameboy.tick_dot();
+        }
+    }
+
+    /// Runs until the first Game Boy reaches vblank, or for a frame worth of dot cycles while
+    /// its LCD is off. The second one runs for the same dot cycles, wherever its frame is
+    pub fn run_frame(&mut self) {
+        for _ in 0..FRAME_DOT_CYCLES {
+            self.tick_dot();
+            if self.gameboys[0].is_vblank_start() {
+                break;
+            }
+        }
+
+        for gameboy in &mut self.gameboys {
+            gameboy.end_frame();
+        }
+    }
+
+    pub fn run_frames(&mut self, frames: u64) {
+        for _ in 0..frames {
+            self.run_frame();
+        }
+    }
+
+    pub fn run_cycles(&mut self, dot_cycles: u64) {
+        for _ in 0..dot_cycles {
+            self.tick_dot();
+        }
+    }
+
+    /// Flushes both battery saves, even if the first one fails
+    pub fn flush_saves(&mut self) -> Result<(), String> {
+        let results = self.gameboys.each_mut().map(Gameboy::flush_save);
+        results.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::constants::{SERIAL_SB_ADDR, SERIAL_SC_ADDR};
+    use crate::console::link_cable::*;
+    use crate::console::serial::Serial;
+    use alloc::boxed::Box;
+
+    fn linked_serials() -> [Serial; 2] {
+        cable().map(|end| {
+            let mut serial = Serial::new();
+            serial.set_device(Box::new(end));
+            serial
+        })
+    }
+
+    // Ticks both ports in lockstep for two transfers, returns the dot each one finished on
+    fn run_transfer(serials: &mut [Serial; 2]) -> [Option<u32>; 2] {
+        let mut finished = [None; 2];
+        for dot in 0..(2 * TRANSFER_DOTS) {
+            for (serial, finished) in serials.iter_mut().zip(finished.iter_mut()) {
+                if serial.tick() {
+                    *finished = Some(dot);
+                }
+            }
+        }
+        finished
+    }
+
+    #[test]
+    fn test_transfer() {
+        let mut serials = linked_serials();
+        serials[0].write_register(SERIAL_SB_ADDR, 0x12);
+        serials[1].write_register(SERIAL_SB_ADDR, 0x34);
+        serials[1].write_register(SERIAL_SC_ADDR, 0x80);
+        serials[1].tick();
+        serials[0].write_register(SERIAL_SC_ADDR, 0x81);
+
+        // Both finish on the master's 8th bit
+        let done = TRANSFER_DOTS - 1;
+        assert_eq!(run_transfer(&mut serials), [Some(done), Some(done)]);
+        assert_eq!(serials[0].read_register(SERIAL_SB_ADDR), 0x34);
+        assert_eq!(serials[1].read_register(SERIAL_SB_ADDR), 0x12);
+    }
+
+    #[test]
+    fn test_nobody_listening() {
+        let mut serials = linked_serials();
+        serials[0].write_register(SERIAL_SB_ADDR, 0x12);
+        serials[1].write_register(SERIAL_SB_ADDR, 0x34);
+        serials[0].write_register(SERIAL_SC_ADDR, 0x81);
+        // Listening only after the transfer started is too late
+        serials[1].write_register(SERIAL_SC_ADDR, 0x80);
+
+        let finished = run_transfer(&mut serials);
+        assert!(finished[0].is_some() && finished[1].is_none());
+        assert_eq!(serials[0].read_register(SERIAL_SB_ADDR), 0xFF);
+        assert_eq!(serials[1].read_register(SERIAL_SB_ADDR), 0x34);
+    }
+}
@@ -1,13 +1,21 @@
 mod audio;
 mod bus;
-mod cartridge;
+pub mod cartridge;
 pub mod constants;
 mod cpu;
-mod gui;
+pub mod gui;
 mod utils;
 
+pub mod debugger;
 mod dma;
+pub mod frontend;
 pub mod gameboy;
+pub mod gbs;
 mod hw_register;
 mod interrupt;
+pub mod link_cable;
+pub mod printer;
+pub mod rewind;
+mod save_state;
+pub mod serial;
 mod timer;
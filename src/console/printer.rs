@@ -0,0 +1,407 @@
+use crate::console::serial::SerialDevice;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+const MAGIC: [u8; 2] = [0x88, 0x33];
+// Answer to the first byte after the checksum, identifies the printer
+const DEVICE_ID: u8 = 0x81;
+
+const COMMAND_INIT: u8 = 0x01;
+const COMMAND_PRINT: u8 = 0x02;
+const COMMAND_DATA: u8 = 0x04;
+const COMMAND_BREAK: u8 = 0x08;
+const COMMAND_STATUS: u8 = 0x0F;
+
+const STATUS_CHECKSUM_ERROR: u8 = 0x01;
+const STATUS_PRINTING: u8 = 0x02;
+const STATUS_IMAGE_FULL: u8 = 0x04;
+const STATUS_UNPROCESSED: u8 = 0x08;
+
+pub const PRINTER_WIDTH: usize = 160;
+const TILES_PER_ROW: usize = PRINTER_WIDTH / 8;
+// A DATA packet holds a band of 2 tile rows, the buffer fits a whole screen
+const BAND_BYTES: usize = 2 * TILES_PER_ROW * 16;
+const BUFFER_BYTES: usize = 9 * BAND_BYTES;
+// Games send 0 for the usual shades
+const DEFAULT_PALETTE: u8 = 0xE4;
+// The head is busy for about a second after a PRINT
+const PRINT_BUSY_DOTS: u32 = 4_194_304;
+
+/// A finished page, `PRINTER_WIDTH` pixels wide with shades from 0 (white) to 3 (black)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintedPage {
+    pub height: usize,
+    pub pixels: Vec<u8>,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Stage {
+    Magic(usize),
+    Command,
+    Compression,
+    LengthLow,
+    LengthHigh,
+    Data,
+    ChecksumLow,
+    ChecksumHigh,
+    DeviceId,
+    Status,
+}
+
+struct PrinterState {
+    stage: Stage,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    data: Vec<u8>,
+    sum: u16,
+    checksum: u16,
+    status: u8,
+    // Decompressed tile data waiting for a PRINT
+    buffer: Vec<u8>,
+    busy_dots: u32,
+    // Strips printed without a feed after them are part of the same page
+    page: Vec<u8>,
+    pages: Vec<PrintedPage>,
+}
+
+impl PrinterState {
+    fn new() -> Self {
+        Self {
+            stage: Stage::Magic(0),
+            command: 0,
+            compressed: false,
+            length: 0,
+            data: Vec::new(),
+            sum: 0,
+            checksum: 0,
+            status: 0,
+            buffer: Vec::new(),
+            busy_dots: 0,
+            page: Vec::new(),
+            pages: Vec::new(),
+        }
+    }
+
+    fn status(&self) -> u8 {
+        let mut status = self.status;
+        if self.busy_dots > 0 {
+            status |= STATUS_PRINTING;
+        }
+        if !self.buffer.is_empty() {
+            status |= STATUS_UNPROCESSED;
+        }
+        status
+    }
+
+    // Returns the byte shifted out while `byte` comes in
+    fn receive(&mut self, byte: u8) -> u8 {
+        let mut answer = 0x00;
+        self.stage = match self.stage {
+            Stage::Magic(index) if byte == MAGIC[index] => match index + 1 {
+                2 => Stage::Command,
+                next => Stage::Magic(next),
+            },
+            Stage::Magic(_) if byte == MAGIC[0] => Stage::Magic(1),
+            Stage::Magic(_) => Stage::Magic(0),
+            Stage::Command => {
+                self.command = byte;
+                self.sum = byte as u16;
+                Stage::Compression
+            }
+            Stage::Compression => {
+                self.compressed = byte & 0x01 != 0;
+                self.sum = self.sum.wrapping_add(byte as u16);
+                Stage::LengthLow
+            }
+            Stage::LengthLow => {
+                self.length = byte as u16;
+                self.sum = self.sum.wrapping_add(byte as u16);
+                Stage::LengthHigh
+            }
+            Stage::LengthHigh => {
+                self.length |= (byte as u16) << 8;
+                self.sum = self.sum.wrapping_add(byte as u16);
+                self.data.clear();
+                match self.length {
+                    0 => Stage::ChecksumLow,
+                    _ => Stage::Data,
+                }
+            }
+            Stage::Data => {
+                self.data.push(byte);
+                self.sum = self.sum.wrapping_add(byte as u16);
+                if self.data.len() == self.length as usize {
+                    Stage::ChecksumLow
+                } else {
+                    Stage::Data
+                }
+            }
+            Stage::ChecksumLow => {
+                self.checksum = byte as u16;
+                Stage::ChecksumHigh
+            }
+            Stage::ChecksumHigh => {
+                self.checksum |= (byte as u16) << 8;
+                if self.checksum == self.sum {
+                    self.status &= !STATUS_CHECKSUM_ERROR;
+                    self.execute();
+                } else {
+                    self.status |= STATUS_CHECKSUM_ERROR;
+                }
+                Stage::DeviceId
+            }
+            Stage::DeviceId => {
+                answer = DEVICE_ID;
+                Stage::Status
+            }
+            Stage::Status => {
+                answer = self.status();
+                Stage::Magic(0)
+            }
+        };
+        answer
+    }
+
+    fn execute(&mut self) {
+        match self.command {
+            COMMAND_INIT => {
+                self.buffer.clear();
+                self.status = 0;
+            }
+            COMMAND_DATA if self.data.is_empty() => self.status |= STATUS_IMAGE_FULL,
+            COMMAND_DATA => {
+                let data = core::mem::take(&mut self.data);
+                if self.compressed {
+                    self.decompress(&data);
+                } else {
+                    self.buffer.extend_from_slice(&data);
+                }
+                self.buffer.truncate(BUFFER_BYTES);
+            }
+            COMMAND_PRINT if self.data.len() >= 4 => {
+                let (sheets, margins, palette) = (self.data[0], self.data[1], self.data[2]);
+                self.print(sheets, margins, palette);
+            }
+            COMMAND_BREAK => {
+                self.buffer.clear();
+                self.status &= !STATUS_IMAGE_FULL;
+            }
+            // STATUS only asks for the reply
+            COMMAND_STATUS => {}
+            _ => {}
+        }
+    }
+
+    // Run length encoding: a control byte with the top bit set repeats the next byte
+    // (control & 0x7F) + 2 times, otherwise (control + 1) bytes follow as they are
+    fn decompress(&mut self, data: &[u8]) {
+        let mut bytes = data.iter().copied();
+        while let Some(control) = bytes.next() {
+            if control & 0x80 != 0 {
+                let Some(byte) = bytes.next() else {
+                    break;
+                };
+                let count = (control & 0x7F) as usize + 2;
+                self.buffer.extend(core::iter::repeat_n(byte, count));
+            } else {
+                self.buffer
+                    .extend(bytes.by_ref().take(control as usize + 1));
+            }
+        }
+    }
+
+    /// Margins are counted in feeds, the upper nibble before the strip and the lower one after
+    /// it. A feed before the strip starts a new page and one after it finishes the page
+    fn print(&mut self, sheets: u8, margins: u8, palette: u8) {
+        let palette = match palette {
+            0 => DEFAULT_PALETTE,
+            palette => palette,
+        };
+        if margins >> 4 > 0 {
+            self.finish_page();
+        }
+        if sheets > 0 {
+            let strip = decode_tiles(&self.buffer, palette);
+            self.page.extend_from_slice(&strip);
+        }
+        if margins & 0x0F > 0 {
+            self.finish_page();
+        }
+
+        self.buffer.clear();
+        self.status &= !STATUS_IMAGE_FULL;
+        self.busy_dots = PRINT_BUSY_DOTS;
+    }
+
+    fn finish_page(&mut self) {
+        if self.page.is_empty() {
+            return;
+        }
+        let pixels = core::mem::take(&mut self.page);
+        self.pages.push(PrintedPage {
+            height: pixels.len() / PRINTER_WIDTH,
+            pixels,
+        });
+    }
+}
+
+// Tiles are laid out row by row, 20 to a row, in the usual 2 bits per pixel format
+fn decode_tiles(data: &[u8], palette: u8) -> Vec<u8> {
+    let tile_rows = data.len() / (TILES_PER_ROW * 16);
+    let mut pixels = Vec::with_capacity(tile_rows * 8 * PRINTER_WIDTH);
+    for tile_row in data.as_chunks::<{ TILES_PER_ROW * 16 }>().0 {
+        for line in 0..8 {
+            for tile in tile_row.as_chunks::<16>().0 {
+                let (low, high) = (tile[line * 2], tile[line * 2 + 1]);
+                for bit in (0..8).rev() {
+                    let color = ((high >> bit) & 1) << 1 | ((low >> bit) & 1);
+                    pixels.push((palette >> (color * 2)) & 0x03);
+                }
+            }
+        }
+    }
+    pixels
+}
+
+/// Game Boy Printer on the link port. Clones share the paper, so one can be plugged in while
+/// another collects the pages
+#[derive(Clone)]
+pub struct Printer {
+    state: Rc<RefCell<PrinterState>>,
+}
+
+impl Default for Printer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Printer {
+    pub fn new() -> Self {
+        Self {
+            state: Rc::new(RefCell::new(PrinterState::new())),
+        }
+    }
+
+    /// Pages finished since the last call
+    pub fn take_pages(&self) -> Vec<PrintedPage> {
+        core::mem::take(&mut self.state.borrow_mut().pages)
+    }
+
+    /// Ends the page being printed, for when the emulation stops before the game fed the paper
+    pub fn cut(&self) {
+        self.state.borrow_mut().finish_page();
+    }
+}
+
+impl SerialDevice for Printer {
+    fn transfer(&mut self, byte: u8) -> Option<u8> {
+        Some(self.state.borrow_mut().receive(byte))
+    }
+
+    fn tick(&mut self) {
+        let mut state = self.state.borrow_mut();
+        state.busy_dots = state.busy_dots.saturating_sub(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::printer::*;
+    use alloc::vec;
+
+    fn packet(command: u8, compressed: bool, data: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x88, 0x33, command, compressed as u8];
+        packet.extend_from_slice(&(data.len() as u16).to_le_bytes());
+        packet.extend_from_slice(data);
+        let sum = packet[2..]
+            .iter()
+            .fold(0u16, |sum, &byte| sum.wrapping_add(byte as u16));
+        packet.extend_from_slice(&sum.to_le_bytes());
+        packet.extend_from_slice(&[0x00, 0x00]);
+        packet
+    }
+
+    // Returns the device id and status the printer answered with
+    fn send(printer: &mut Printer, packet: &[u8]) -> (u8, u8) {
+        let answers: Vec<u8> = packet
+            .iter()
+            .map(|&byte| printer.transfer(byte).unwrap())
+            .collect();
+        (answers[answers.len() - 2], answers[answers.len() - 1])
+    }
+
+    #[test]
+    fn test_print_page() {
+        let mut printer = Printer::new();
+        assert_eq!(
+            send(&mut printer, &packet(COMMAND_INIT, false, &[])),
+            (0x81, 0)
+        );
+
+        // A band where every tile is color 3 on its first line, compressed into two runs
+        let band: Vec<u8> = (0..BAND_BYTES)
+            .map(|i| if i % 16 < 2 { 0xFF } else { 0x00 })
+            .collect();
+        let mut compressed = Vec::new();
+        for _ in 0..(BAND_BYTES / 16) {
+            compressed.extend_from_slice(&[0x80, 0xFF, 0x8C, 0x00]);
+        }
+        let (_, status) = send(&mut printer, &packet(COMMAND_DATA, true, &compressed));
+        assert_eq!(status, STATUS_UNPROCESSED);
+        send(&mut printer, &packet(COMMAND_DATA, false, &band));
+        let (_, status) = send(&mut printer, &packet(COMMAND_DATA, false, &[]));
+        assert_eq!(status, STATUS_UNPROCESSED | STATUS_IMAGE_FULL);
+
+        // One sheet, no feed before and some after, inverted palette
+        let (_, status) = send(
+            &mut printer,
+            &packet(COMMAND_PRINT, false, &[1, 0x03, 0x1B, 0x40]),
+        );
+        assert_eq!(status, STATUS_PRINTING);
+        for _ in 0..PRINT_BUSY_DOTS {
+            printer.tick();
+        }
+        assert_eq!(
+            send(&mut printer, &packet(COMMAND_STATUS, false, &[])),
+            (0x81, 0)
+        );
+
+        let pages = printer.take_pages();
+        assert_eq!(pages.len(), 1);
+        assert_eq!(pages[0].height, 32);
+        // Color 3 prints white with this palette, color 0 black
+        assert!(
+            pages[0].pixels[..PRINTER_WIDTH]
+                .iter()
+                .all(|&shade| shade == 0)
+        );
+        assert!(
+            pages[0].pixels[PRINTER_WIDTH..2 * PRINTER_WIDTH]
+                .iter()
+                .all(|&shade| shade == 3)
+        );
+        assert_eq!(
+            pages[0].pixels[16 * PRINTER_WIDTH..],
+            pages[0].pixels[..16 * PRINTER_WIDTH]
+        );
+        assert!(printer.take_pages().is_empty());
+    }
+
+    #[test]
+    fn test_checksum_error() {
+        let mut printer = Printer::new();
+        let mut bad = packet(COMMAND_DATA, false, &[0x12; 16]);
+        let checksum = bad.len() - 4;
+        bad[checksum] ^= 0xFF;
+        let (_, status) = send(&mut printer, &bad);
+        assert_eq!(status, STATUS_CHECKSUM_ERROR);
+
+        // Garbage before the magic bytes is skipped
+        let mut status_packet = vec![0x00, 0x88, 0x12];
+        status_packet.extend(packet(COMMAND_STATUS, false, &[]));
+        assert_eq!(send(&mut printer, &status_packet), (0x81, 0));
+    }
+}
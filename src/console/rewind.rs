@@ -0,0 +1,238 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+pub struct RewindConfig {
+    /// A snapshot is taken every `interval_frames` frames
+    pub interval_frames: u64,
+    /// Upper bound for the memory used by the compressed history, 0 disables rewinding
+    pub budget_bytes: usize,
+}
+
+impl Default for RewindConfig {
+    fn default() -> Self {
+        Self {
+            interval_frames: 2,
+            budget_bytes: 32 * 1024 * 1024,
+        }
+    }
+}
+
+/// History of save states. Only the newest one is kept whole, every older one is stored as the
+/// run length encoded XOR with its successor, which is mostly zeros between nearby frames
+pub struct Rewind {
+    config: RewindConfig,
+    newest: Option<Vec<u8>>,
+    deltas: VecDeque<Vec<u8>>,
+    used_bytes: usize,
+    // Frames played backwards since the last snapshot was recorded
+    playback_frames: u64,
+}
+
+impl Rewind {
+    pub fn new(config: RewindConfig) -> Self {
+        Self {
+            config,
+            newest: None,
+            deltas: VecDeque::new(),
+            used_bytes: 0,
+            playback_frames: 0,
+        }
+    }
+
+    pub fn should_snapshot(&self, frame: u64) -> bool {
+        self.config.budget_bytes > 0 && frame.is_multiple_of(self.config.interval_frames.max(1))
+    }
+
+    pub fn push(&mut self, state: Vec<u8>) {
+        if let Some(previous) = self.newest.take() {
+            self.used_bytes -= previous.len();
+            if previous.len() == state.len() {
+                let delta = encode_delta(&previous, &state);
+                self.used_bytes += delta.len();
+                self.deltas.push_back(delta);
+            } else {
+                self.clear();
+            }
+        }
+        self.used_bytes += state.len();
+        self.newest = Some(state);
+        self.playback_frames = 0;
+
+        while self.used_bytes > self.config.budget_bytes {
+            let Some(oldest) = self.deltas.pop_front() else {
+                break;
+            };
+            self.used_bytes -= oldest.len();
+        }
+    }
+
+    /// Removes and returns the newest snapshot, the one before it becomes the newest
+    pub fn pop(&mut self) -> Option<Vec<u8>> {
+        let newest = self.newest.take()?;
+        self.used_bytes -= newest.len();
+
+        if let Some(delta) = self.deltas.pop_back() {
+            self.used_bytes -= delta.len();
+            let mut previous = newest.clone();
+            decode_delta(&delta, &mut previous);
+            self.used_bytes += previous.len();
+            self.newest = Some(previous);
+        }
+        Some(newest)
+    }
+
+    /// Counts a frame played backwards, true when the next older snapshot is due. Each snapshot
+    /// stays for `interval_frames` frames so the history plays back at the speed it was recorded
+    pub fn next_playback_frame(&mut self) -> bool {
+        let due = self
+            .playback_frames
+            .is_multiple_of(self.config.interval_frames.max(1));
+        self.playback_frames += 1;
+        due
+    }
+
+    pub fn clear(&mut self) {
+        self.newest = None;
+        self.deltas.clear();
+        self.used_bytes = 0;
+        self.playback_frames = 0;
+    }
+
+    pub fn len(&self) -> usize {
+        self.newest.as_ref().map_or(0, |_| self.deltas.len() + 1)
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> usize {
+    let mut value = 0;
+    let mut shift = 0;
+    while let Some(&byte) = data.get(*pos) {
+        *pos += 1;
+        value |= ((byte & 0x7F) as usize) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+    }
+    value
+}
+
+// Runs of zeros shorter than this are cheaper to copy as literals
+const MIN_ZERO_RUN: usize = 4;
+
+/// XOR of `older` and `newer` as a list of (zero run length, literal length, literals)
+fn encode_delta(older: &[u8], newer: &[u8]) -> Vec<u8> {
+    let xor = |i: usize| older[i] ^ newer[i];
+    let len = older.len();
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while i < len {
+        let zeros_start = i;
+        while i < len && xor(i) == 0 {
+            i += 1;
+        }
+        let zeros = i - zeros_start;
+
+        let literals_start = i;
+        let mut zero_run = 0;
+        while i < len && zero_run < MIN_ZERO_RUN {
+            zero_run = if xor(i) == 0 { zero_run + 1 } else { 0 };
+            i += 1;
+        }
+        // Leave the trailing zeros for the next run
+        if zero_run == MIN_ZERO_RUN {
+            i -= zero_run;
+        }
+
+        write_varint(&mut out, zeros);
+        write_varint(&mut out, i - literals_start);
+        out.extend((literals_start..i).map(xor));
+    }
+    out
+}
+
+/// Turns `state` into the older state the delta was computed against
+fn decode_delta(delta: &[u8], state: &mut [u8]) {
+    let mut pos = 0;
+    let mut i = 0;
+    while pos < delta.len() {
+        i += read_varint(delta, &mut pos);
+        let literals = read_varint(delta, &mut pos);
+        for &byte in &delta[pos..pos + literals] {
+            state[i] ^= byte;
+            i += 1;
+        }
+        pos += literals;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::rewind::*;
+
+    fn state(seed: u8) -> Vec<u8> {
+        let mut state = vec![0u8; 0x1000];
+        state[0x10] = seed;
+        state[0x800..0x808].fill(seed.wrapping_mul(3));
+        state[0xFFF] = seed ^ 0x55;
+        state
+    }
+
+    #[test]
+    fn test_rewind_pops_in_reverse_order() {
+        let mut rewind = Rewind::new(RewindConfig::default());
+        for seed in 0..10 {
+            rewind.push(state(seed));
+        }
+        assert_eq!(rewind.len(), 10);
+
+        for seed in (0..10).rev() {
+            assert_eq!(rewind.pop(), Some(state(seed)));
+        }
+        assert_eq!(rewind.pop(), None);
+        assert_eq!(rewind.used_bytes, 0);
+    }
+
+    #[test]
+    fn test_rewind_playback_speed() {
+        let mut rewind = Rewind::new(RewindConfig {
+            interval_frames: 3,
+            ..RewindConfig::default()
+        });
+        rewind.push(state(0));
+        let due: Vec<bool> = (0..7).map(|_| rewind.next_playback_frame()).collect();
+        assert_eq!(due, [true, false, false, true, false, false, true]);
+
+        // Recording again starts a new playback
+        rewind.push(state(1));
+        assert!(rewind.next_playback_frame());
+    }
+
+    #[test]
+    fn test_rewind_budget() {
+        let budget = 0x1000 + 64;
+        let mut rewind = Rewind::new(RewindConfig {
+            interval_frames: 1,
+            budget_bytes: budget,
+        });
+        for seed in 0..100 {
+            rewind.push(state(seed));
+            assert!(rewind.used_bytes <= budget);
+        }
+
+        let kept = rewind.len();
+        assert!(kept > 1 && kept < 100);
+        for seed in (100 - kept as u8..100).rev() {
+            assert_eq!(rewind.pop(), Some(state(seed)));
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use crate::console::cartridge::header::CartridgeHeader;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+const STATE_MAGIC: [u8; 4] = *b"RGBS";
+// Bump whenever the layout of any component changes, older states are refused
+pub const STATE_VERSION: u16 = 5;
+const STATE_TITLE_SIZE: usize = 16;
+
+/// Little endian byte sink every component appends its state to, in a fixed order
+#[derive(Default)]
+pub struct StateWriter {
+    data: Vec<u8>,
+}
+
+impl StateWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_u8(&mut self, value: u8) {
+        self.data.push(value);
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.data.push(value as u8);
+    }
+
+    pub fn write_u16(&mut self, value: u16) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        self.data.extend_from_slice(&value.to_le_bytes());
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) {
+        self.data.extend_from_slice(bytes);
+    }
+
+    /// Length prefixed, for buffers whose size depends on the cartridge
+    pub fn write_vec(&mut self, bytes: &[u8]) {
+        self.write_u64(bytes.len() as u64);
+        self.write_bytes(bytes);
+    }
+
+    /// Identifies the ROM so a state is never loaded into a different game
+    pub fn write_header(&mut self, header: &CartridgeHeader) {
+        self.write_bytes(&STATE_MAGIC);
+        self.write_u16(STATE_VERSION);
+        self.write_bytes(&state_title(header));
+        self.write_u16(header.global_checksum);
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.data
+    }
+}
+
+pub struct StateReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> StateReader<'a> {
+    pub fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len());
+        let Some(end) = end else {
+            return Err(String::from("Save state is truncated"));
+        };
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    pub fn read_u8(&mut self) -> Result<u8, String> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    pub fn read_bool(&mut self) -> Result<bool, String> {
+        Ok(self.read_u8()? != 0)
+    }
+
+    pub fn read_u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+
+    pub fn read_into(&mut self, out: &mut [u8]) -> Result<(), String> {
+        out.copy_from_slice(self.read_bytes(out.len())?);
+        Ok(())
+    }
+
+    /// Reads a buffer written with `write_vec`, its length has to match `out`
+    pub fn read_vec_into(&mut self, out: &mut [u8]) -> Result<(), String> {
+        let len = self.read_u64()?;
+        if len != out.len() as u64 {
+            return Err(format!(
+                "Save state buffer is {} bytes, expected {}",
+                len,
+                out.len()
+            ));
+        }
+        self.read_into(out)
+    }
+
+    pub fn read_header(&mut self, header: &CartridgeHeader) -> Result<(), String> {
+        if self.read_bytes(STATE_MAGIC.len())? != STATE_MAGIC {
+            return Err(String::from("Not a save state"));
+        }
+        let version = self.read_u16()?;
+        if version != STATE_VERSION {
+            return Err(format!("Unsupported save state version {}", version));
+        }
+        let title = self.read_bytes(STATE_TITLE_SIZE)?;
+        let global_checksum = self.read_u16()?;
+        if title != state_title(header) || global_checksum != header.global_checksum {
+            return Err(String::from("Save state belongs to a different ROM"));
+        }
+        Ok(())
+    }
+
+    pub fn finish(&self) -> Result<(), String> {
+        if self.pos != self.data.len() {
+            return Err(String::from("Save state has trailing data"));
+        }
+        Ok(())
+    }
+}
+
+fn state_title(header: &CartridgeHeader) -> [u8; STATE_TITLE_SIZE] {
+    let mut title = [0u8; STATE_TITLE_SIZE];
+    let len = header.title.len().min(STATE_TITLE_SIZE);
+    title[..len].copy_from_slice(&header.title.as_bytes()[..len]);
+    title
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::bus::Bus;
+    use crate::console::constants::*;
+    use crate::console::save_state::*;
+
+    fn test_rom(title: &[u8]) -> Vec<u8> {
+        let mut rom = vec![0u8; 4 * ROM_BANK_SIZE];
+        for bank in 0..4 {
+            rom[bank * ROM_BANK_SIZE] = bank as u8;
+        }
+        rom[TITLE_ADDR..TITLE_ADDR + title.len()].copy_from_slice(title);
+        rom[CARTRIDGE_TYPE_ADDR] = 0x13;
+        rom[ROM_SIZE_ADDR] = 0x01;
+        rom[RAM_SIZE_ADDR] = 0x03;
+        rom
+    }
+
+    fn save_bus(bus: &Bus) -> Vec<u8> {
+        let mut state = StateWriter::new();
+        state.write_header(bus.cartridge().header().unwrap());
+        bus.save_state(&mut state);
+        state.into_bytes()
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let mut bus = Bus::new();
+        bus.load_rom(&test_rom(b"STATE")).unwrap();
+        bus.write_to_8b(0x0000, 0x0A);
+        bus.write_to_8b(0x2000, 0x02);
+        bus.write_to_8b(EXT_RAM_BEGIN, 0x42);
+        bus.write_to_8b(0xC123, 0x37);
+        bus.write_to_8b(VRAM_BEGIN + 0x10, 0x99);
+        let data = save_bus(&bus);
+
+        let mut restored = Bus::new();
+        restored.load_rom(&test_rom(b"STATE")).unwrap();
+        let mut state = StateReader::new(&data);
+        state
+            .read_header(restored.cartridge().header().unwrap())
+            .unwrap();
+        restored.load_state(&mut state).unwrap();
+        state.finish().unwrap();
+
+        assert_eq!(restored.read_from_8b(ROM_BANK_N_BEGIN), 0x02);
+        assert_eq!(restored.read_from_8b(EXT_RAM_BEGIN), 0x42);
+        assert_eq!(restored.read_from_8b(0xC123), 0x37);
+        assert_eq!(restored.read_from_8b(VRAM_BEGIN + 0x10), 0x99);
+        assert_eq!(save_bus(&restored), data);
+    }
+
+    #[test]
+    fn test_state_rejects_other_rom() {
+        let mut bus = Bus::new();
+        bus.load_rom(&test_rom(b"STATE")).unwrap();
+        let data = save_bus(&bus);
+
+        let mut other = Bus::new();
+        other.load_rom(&test_rom(b"OTHER")).unwrap();
+        let mut state = StateReader::new(&data);
+        assert!(
+            state
+                .read_header(other.cartridge().header().unwrap())
+                .is_err()
+        );
+
+        let mut state = StateReader::new(&data[..data.len() / 2]);
+        state
+            .read_header(bus.cartridge().header().unwrap())
+            .unwrap();
+        assert!(bus.load_state(&mut state).is_err());
+    }
+}
@@ -0,0 +1,319 @@
+use crate::console::constants::{SERIAL_SB_ADDR, SERIAL_SC_ADDR};
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+
+// SC bit 7 starts a transfer and stays set until it is done, bit 0 selects the internal clock
+const SC_TRANSFER: u8 = 0x80;
+const SC_INTERNAL_CLOCK: u8 = 0x01;
+// Bits 1-6 do not exist on the DMG and read back as 1
+const SC_READ_MASK: u8 = 0x7E;
+// The internal clock runs at 8192 Hz
+pub(crate) const DOTS_PER_BIT: u32 = 512;
+
+/// Anything plugged into the link port
+pub trait SerialDevice {
+    /// The Game Boy starts a transfer on its internal clock with `byte` in SB. Returns the byte
+    /// sent back, which is shifted in over the next 8 bit periods. Devices that cannot answer
+    /// right away return `None` and the clock holds until `poll_transfer` has the answer
+    fn transfer(&mut self, byte: u8) -> Option<u8>;
+
+    /// Polled every dot while a transfer on the internal clock waits for its answer
+    fn poll_transfer(&mut self) -> Option<u8> {
+        None
+    }
+
+    /// Polled every dot while the Game Boy waits for the other side to clock a transfer, with
+    /// `byte` in SB. Returns the byte sent back once that happened
+    fn external_transfer(&mut self, _byte: u8) -> Option<u8> {
+        None
+    }
+
+    /// Called every dot before the transfer is advanced, for devices that keep time
+    fn tick(&mut self) {}
+}
+
+/// Nothing connected, the data line is pulled high and external clocks never come
+pub struct NullSerial;
+
+impl SerialDevice for NullSerial {
+    fn transfer(&mut self, _byte: u8) -> Option<u8> {
+        Some(0xFF)
+    }
+}
+
+/// Collects every byte the Game Boy sends on its internal clock, like the results test ROMs
+/// print. Clones share the bytes, so one can be plugged in while another reads them
+#[derive(Clone, Default)]
+pub struct SerialCapture {
+    bytes: Rc<RefCell<Vec<u8>>>,
+}
+
+impl SerialCapture {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        self.bytes.borrow().clone()
+    }
+
+    /// The bytes as Latin-1 text
+    pub fn text(&self) -> String {
+        self.bytes.borrow().iter().map(|&byte| byte as char).collect()
+    }
+
+    pub fn clear(&self) {
+        self.bytes.borrow_mut().clear();
+    }
+}
+
+impl SerialDevice for SerialCapture {
+    fn transfer(&mut self, byte: u8) -> Option<u8> {
+        self.bytes.borrow_mut().push(byte);
+        Some(0xFF)
+    }
+}
+
+/// SB and SC, shifting a byte out and in one bit at a time
+pub struct Serial {
+    sb: u8,
+    sc: u8,
+    device: Box<dyn SerialDevice>,
+    // Byte from the device, shifted into SB from the top bit down. `None` while the device
+    // has not answered yet
+    incoming: Option<u8>,
+    bits_left: u8,
+    bit_dots: u32,
+}
+
+impl Serial {
+    pub fn new() -> Self {
+        Self {
+            sb: 0,
+            sc: 0,
+            device: Box::new(NullSerial),
+            incoming: Some(0xFF),
+            bits_left: 0,
+            bit_dots: 0,
+        }
+    }
+
+    pub fn set_device(&mut self, device: Box<dyn SerialDevice>) {
+        self.device = device;
+    }
+
+    pub fn read_register(&self, addr: u16) -> u8 {
+        match addr {
+            SERIAL_SB_ADDR => self.sb,
+            SERIAL_SC_ADDR => self.sc | SC_READ_MASK,
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write_register(&mut self, addr: u16, value: u8) {
+        match addr {
+            SERIAL_SB_ADDR => self.sb = value,
+            SERIAL_SC_ADDR => {
+                self.sc = value & (SC_TRANSFER | SC_INTERNAL_CLOCK);
+                self.bits_left = 8;
+                self.bit_dots = 0;
+                if self.sc == SC_TRANSFER | SC_INTERNAL_CLOCK {
+                    self.incoming = self.device.transfer(self.sb);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Advances a running transfer by one dot. Returns true when it finished, which raises the
+    /// serial interrupt
+    pub fn tick(&mut self) -> bool {
+        self.device.tick();
+        if self.sc & SC_TRANSFER == 0 {
+            return false;
+        }
+
+        if self.sc & SC_INTERNAL_CLOCK == 0 {
+            let Some(byte) = self.device.external_transfer(self.sb) else {
+                return false;
+            };
+            self.sb = byte;
+            self.sc &= !SC_TRANSFER;
+            return true;
+        }
+
+        let incoming = match self.incoming {
+            Some(incoming) => incoming,
+            None => match self.device.poll_transfer() {
+                Some(incoming) => *self.incoming.insert(incoming),
+                None => return false,
+            },
+        };
+        self.bit_dots += 1;
+        if self.bit_dots < DOTS_PER_BIT {
+            return false;
+        }
+        self.bit_dots = 0;
+        self.bits_left = self.bits_left.saturating_sub(1);
+        self.sb = (self.sb << 1) | ((incoming >> self.bits_left) & 1);
+        if self.bits_left > 0 {
+            return false;
+        }
+        self.sc &= !SC_TRANSFER;
+        true
+    }
+
+    /// The device is left out, it is whatever is plugged in at the time
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u8(self.sb);
+        state.write_u8(self.sc);
+        state.write_bool(self.incoming.is_some());
+        state.write_u8(self.incoming.unwrap_or(0xFF));
+        state.write_u8(self.bits_left);
+        state.write_u32(self.bit_dots);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self.sb = state.read_u8()?;
+        self.sc = state.read_u8()? & (SC_TRANSFER | SC_INTERNAL_CLOCK);
+        let answered = state.read_bool()?;
+        let incoming = state.read_u8()?;
+        self.incoming = answered.then_some(incoming);
+        self.bits_left = state.read_u8()?.min(8);
+        self.bit_dots = state.read_u32()? % DOTS_PER_BIT;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::console::serial::*;
+
+    struct Echo {
+        sent: Rc<RefCell<Vec<u8>>>,
+    }
+
+    impl SerialDevice for Echo {
+        fn transfer(&mut self, byte: u8) -> Option<u8> {
+            self.sent.borrow_mut().push(byte);
+            Some(!byte)
+        }
+
+        fn external_transfer(&mut self, byte: u8) -> Option<u8> {
+            self.sent.borrow_mut().push(byte);
+            Some(0x42)
+        }
+    }
+
+    fn run_until_done(serial: &mut Serial) -> u32 {
+        let mut dots = 1;
+        while !serial.tick() {
+            dots += 1;
+            assert!(dots <= 8 * DOTS_PER_BIT);
+        }
+        dots
+    }
+
+    #[test]
+    fn test_nothing_connected() {
+        let mut serial = Serial::new();
+        serial.write_register(SERIAL_SB_ADDR, 0x12);
+        serial.write_register(SERIAL_SC_ADDR, 0x81);
+        assert_eq!(serial.read_register(SERIAL_SC_ADDR), 0xFF);
+
+        assert_eq!(run_until_done(&mut serial), 8 * DOTS_PER_BIT);
+        assert_eq!(serial.read_register(SERIAL_SB_ADDR), 0xFF);
+        assert_eq!(serial.read_register(SERIAL_SC_ADDR), 0x7F);
+        assert!(!serial.tick());
+    }
+
+    #[test]
+    fn test_internal_clock_shifts_bits() {
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        let mut serial = Serial::new();
+        serial.set_device(Box::new(Echo { sent: sent.clone() }));
+        serial.write_register(SERIAL_SB_ADDR, 0xA0);
+        serial.write_register(SERIAL_SC_ADDR, 0x81);
+
+        // After two bit periods the top two bits went out and the first two of !0xA0 came in
+        for _ in 0..(2 * DOTS_PER_BIT) {
+            serial.tick();
+        }
+        assert_eq!(serial.read_register(SERIAL_SB_ADDR), 0x81);
+
+        run_until_done(&mut serial);
+        assert_eq!(serial.read_register(SERIAL_SB_ADDR), 0x5F);
+        assert_eq!(*sent.borrow(), [0xA0]);
+    }
+
+    struct Late {
+        answer: Rc<RefCell<Option<u8>>>,
+    }
+
+    impl SerialDevice for Late {
+        fn transfer(&mut self, _byte: u8) -> Option<u8> {
+            None
+        }
+
+        fn poll_transfer(&mut self) -> Option<u8> {
+            self.answer.borrow_mut().take()
+        }
+    }
+
+    #[test]
+    fn test_clock_holds_until_answered() {
+        let answer = Rc::new(RefCell::new(None));
+        let mut serial = Serial::new();
+        serial.set_device(Box::new(Late {
+            answer: answer.clone(),
+        }));
+        serial.write_register(SERIAL_SB_ADDR, 0x12);
+        serial.write_register(SERIAL_SC_ADDR, 0x81);
+        for _ in 0..(16 * DOTS_PER_BIT) {
+            assert!(!serial.tick());
+        }
+        assert_eq!(serial.read_register(SERIAL_SB_ADDR), 0x12);
+
+        *answer.borrow_mut() = Some(0x34);
+        assert_eq!(run_until_done(&mut serial), 8 * DOTS_PER_BIT);
+        assert_eq!(serial.read_register(SERIAL_SB_ADDR), 0x34);
+    }
+
+    #[test]
+    fn test_external_clock() {
+        let mut serial = Serial::new();
+        serial.write_register(SERIAL_SB_ADDR, 0x12);
+        serial.write_register(SERIAL_SC_ADDR, 0x80);
+        // Nothing connected never clocks the transfer
+        for _ in 0..(16 * DOTS_PER_BIT) {
+            assert!(!serial.tick());
+        }
+
+        let sent = Rc::new(RefCell::new(Vec::new()));
+        serial.set_device(Box::new(Echo { sent: sent.clone() }));
+        assert!(serial.tick());
+        assert_eq!(serial.read_register(SERIAL_SB_ADDR), 0x42);
+        assert_eq!(*sent.borrow(), [0x12]);
+    }
+
+    #[test]
+    fn test_capture() {
+        let capture = SerialCapture::new();
+        let mut serial = Serial::new();
+        serial.set_device(Box::new(capture.clone()));
+        for &byte in b"Passed\n" {
+            serial.write_register(SERIAL_SB_ADDR, byte);
+            serial.write_register(SERIAL_SC_ADDR, 0x81);
+            run_until_done(&mut serial);
+            assert_eq!(serial.read_register(SERIAL_SB_ADDR), 0xFF);
+        }
+        assert_eq!(capture.text(), "Passed\n");
+
+        capture.clear();
+        assert!(capture.bytes().is_empty());
+    }
+}
@@ -2,6 +2,8 @@ use crate::console::bus::Bus;
 use crate::console::constants::*;
 use crate::console::hw_register::HwRegister;
 use crate::console::interrupt::Interrupt;
+use crate::console::save_state::{StateReader, StateWriter};
+use alloc::string::String;
 
 #[derive(Default)]
 pub struct Timer {
@@ -17,6 +19,19 @@ impl Timer {
         Self::default()
     }
 
+    pub fn save_state(&self, state: &mut StateWriter) {
+        state.write_u64(self._c_cycles);
+        state.write_u64(self._c_cycles_since_div);
+        state.write_u64(self._c_cycles_since_tima);
+    }
+
+    pub fn load_state(&mut self, state: &mut StateReader) -> Result<(), String> {
+        self._c_cycles = state.read_u64()?;
+        self._c_cycles_since_div = state.read_u64()?;
+        self._c_cycles_since_tima = state.read_u64()?;
+        Ok(())
+    }
+
     fn inc_div(&mut self, bus: &mut Bus) {
         while self._c_cycles_since_div >= TIMER_DIV_INC_RATE {
             bus.inc_div();
@@ -0,0 +1,511 @@
+use crate::arg_parse::args::Args;
+use crate::frontend::gdb::GdbStub;
+use rustemu::{
+    AudioSink, Breakpoint, DebugBreak, Gameboy, Hotkey, InputSource, RunTo, VideoSink, WatchKind,
+    Watchpoint,
+};
+use std::io::{self, BufRead, Write};
+
+// The `io` watch range, the hardware registers without HRAM and IE
+const IO_BEGIN: u16 = 0xFF00;
+const IO_END: u16 = 0xFF7F;
+const HEXDUMP_WIDTH: u16 = 16;
+const DEFAULT_HEXDUMP_LEN: u16 = 0x40;
+// Instructions shown before and after the PC by `disasm`
+const DISASM_BEFORE: usize = 3;
+const DISASM_AFTER: usize = 6;
+const MAX_INSTRUCTION_SIZE: u16 = 3;
+// Shown instead of loading a state or rewinding while linked to another emulator
+const LINK_CABLE_MESSAGE: &str = "Loading states and rewind are not available over a link cable";
+
+const HELP: &str = "Commands, addresses are hex:
+  b, break [bank:]addr    stop before the instruction at addr, lists breakpoints without one
+  clear [[bank:]addr]     remove a breakpoint, or all of them
+  w, watch r|w|rw addr[-end]|io
+                          stop after an instruction reads or writes the range, lists without one
+  unwatch [addr|io]       remove the watchpoints starting at addr, or all of them
+  s, step [count]         run single instructions
+  n, next                 step over calls and RSTs
+  f, finish               run until the current function returns
+  c, continue             run until the next stop
+  r, regs                 show the registers and flags
+  x addr [len]            hexdump memory
+  d, disasm [addr]        disassemble around addr, the PC by default
+  q, quit                 close the emulator
+An empty line repeats the last command";
+
+/// Takes over running the frames to stop wherever the user asked for, the REPL on stdin or a
+/// remote GDB
+pub trait DebugFrontend {
+    /// Runs a frame, or what is left of it, with any stops on the way. Returns false to quit
+    fn run_frame(&mut self, gameboy: &mut Gameboy, video: &mut dyn VideoSink) -> bool;
+
+    /// Stops before the next frame, for the debug hotkey
+    fn pause(&mut self) -> Result<(), String>;
+}
+
+/// Breaks into a REPL on stdin when a breakpoint or watchpoint is hit, F12 is pressed or right
+/// at the start
+pub struct Debugger {
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    // Prompt before the next frame
+    pause: bool,
+    // `next` or `finish` is still waiting to be reached
+    running_to: bool,
+    last_command: String,
+    detached: bool,
+}
+
+/// What the prompt asks the run loop to do
+enum Resume {
+    Run,
+    Quit,
+}
+
+impl Debugger {
+    pub fn new(start_paused: bool) -> Self {
+        Self {
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            pause: start_paused,
+            running_to: false,
+            last_command: String::new(),
+            detached: false,
+        }
+    }
+
+    fn armed(&self) -> bool {
+        !self.breakpoints.is_empty() || !self.watchpoints.is_empty() || self.running_to
+    }
+
+    fn prompt(&mut self, gameboy: &mut Gameboy, stop: Option<DebugBreak>) -> Resume {
+        self.running_to = false;
+        gameboy.set_run_to(None);
+        if let Some(stop) = stop {
+            println!("{}", stop);
+        }
+        println!("{}", gameboy.registers());
+        print_instruction(gameboy, gameboy.registers().pc, &self.breakpoints);
+
+        let stdin = io::stdin();
+        loop {
+            print!("(debug) ");
+            let _ = io::stdout().flush();
+            let mut line = String::new();
+            if stdin.lock().read_line(&mut line).unwrap_or(0) == 0 {
+                // Nobody to type commands, let the game run on its own
+                println!();
+                self.detach(gameboy);
+                return Resume::Run;
+            }
+            let mut command = line.trim().to_string();
+            if command.is_empty() {
+                command = self.last_command.clone();
+            }
+            self.last_command = command.clone();
+            match self.execute(gameboy, &command) {
+                Ok(Some(resume)) => return resume,
+                Ok(None) => {}
+                Err(e) => println!("{}", e),
+            }
+        }
+    }
+
+    fn detach(&mut self, gameboy: &mut Gameboy) {
+        self.detached = true;
+        self.breakpoints.clear();
+        self.watchpoints.clear();
+        gameboy.set_breakpoints(&[]);
+        gameboy.set_watchpoints(&[]);
+    }
+
+    fn execute(&mut self, gameboy: &mut Gameboy, command: &str) -> Result<Option<Resume>, String> {
+        let mut words = command.split_whitespace();
+        let Some(name) = words.next() else {
+            return Ok(None);
+        };
+        let args: Vec<&str> = words.collect();
+        match name {
+            "h" | "help" => println!("{}", HELP),
+            "b" | "break" => match args.first() {
+                Some(arg) => {
+                    let breakpoint = parse_breakpoint(arg)?;
+                    if !self.breakpoints.contains(&breakpoint) {
+                        self.breakpoints.push(breakpoint);
+                    }
+                    gameboy.set_breakpoints(&self.breakpoints);
+                }
+                None => {
+                    for breakpoint in &self.breakpoints {
+                        println!("{}", breakpoint);
+                    }
+                }
+            },
+            "clear" => {
+                match args.first() {
+                    Some(arg) => {
+                        let breakpoint = parse_breakpoint(arg)?;
+                        let count = self.breakpoints.len();
+                        self.breakpoints.retain(|&b| b != breakpoint);
+                        if self.breakpoints.len() == count {
+                            return Err(format!("No breakpoint at {}", breakpoint));
+                        }
+                    }
+                    None => self.breakpoints.clear(),
+                }
+                gameboy.set_breakpoints(&self.breakpoints);
+            }
+            "w" | "watch" => match args[..] {
+                [kind, range] => {
+                    let watchpoint = parse_watchpoint(kind, range)?;
+                    self.watchpoints.push(watchpoint);
+                    gameboy.set_watchpoints(&self.watchpoints);
+                }
+                [] => {
+                    for watchpoint in &self.watchpoints {
+                        println!("{}", watchpoint);
+                    }
+                }
+                _ => return Err("Usage: watch r|w|rw addr[-end]|io".into()),
+            },
+            "unwatch" => {
+                match args.first() {
+                    Some(arg) => {
+                        let (start, _) = parse_range(arg)?;
+                        let count = self.watchpoints.len();
+                        self.watchpoints.retain(|w| w.start != start);
+                        if self.watchpoints.len() == count {
+                            return Err(format!("No watchpoint at {:04X}", start));
+                        }
+                    }
+                    None => self.watchpoints.clear(),
+                }
+                gameboy.set_watchpoints(&self.watchpoints);
+            }
+            "s" | "step" => {
+                let count = match args.first() {
+                    Some(arg) => arg
+                        .parse::<u32>()
+                        .map_err(|_| format!("Invalid step count: {}", arg))?,
+                    None => 1,
+                };
+                self.step(gameboy, count);
+            }
+            "n" | "next" => {
+                let pc = gameboy.registers().pc;
+                if !is_call(gameboy.peek(pc)) {
+                    self.step(gameboy, 1);
+                    return Ok(None);
+                }
+                let (_, size) = gameboy.disassemble(pc);
+                let sp = gameboy.registers().sp;
+                return Ok(Some(self.run_to(
+                    gameboy,
+                    RunTo::Return {
+                        pc: pc.wrapping_add(size),
+                        sp,
+                    },
+                )));
+            }
+            "f" | "finish" => {
+                let sp = gameboy.registers().sp;
+                return Ok(Some(self.run_to(gameboy, RunTo::StackAbove(sp))));
+            }
+            "c" | "continue" => return Ok(Some(self.resume(gameboy))),
+            "r" | "regs" => {
+                println!("{}", gameboy.registers());
+                println!("ROM bank {:02X}", gameboy.rom_bank());
+            }
+            "x" => {
+                let addr = parse_addr(args.first().ok_or("Usage: x addr [len]")?)?;
+                let len = match args.get(1) {
+                    Some(len) => parse_addr(len)?,
+                    None => DEFAULT_HEXDUMP_LEN,
+                };
+                hexdump(gameboy, addr, len);
+            }
+            "d" | "disasm" => {
+                let addr = match args.first() {
+                    Some(arg) => parse_addr(arg)?,
+                    None => gameboy.registers().pc,
+                };
+                self.disassemble_around(gameboy, addr);
+            }
+            "q" | "quit" => return Ok(Some(Resume::Quit)),
+            _ => return Err(format!("Unknown command {}, try help", name)),
+        }
+        Ok(None)
+    }
+
+    fn step(&mut self, gameboy: &mut Gameboy, count: u32) {
+        for _ in 0..count {
+            if let Some(stop) = gameboy.debug_step() {
+                println!("{}", stop);
+                break;
+            }
+        }
+        print_instruction(gameboy, gameboy.registers().pc, &self.breakpoints);
+    }
+
+    fn run_to(&mut self, gameboy: &mut Gameboy, run_to: RunTo) -> Resume {
+        gameboy.set_run_to(Some(run_to));
+        self.running_to = true;
+        self.resume(gameboy)
+    }
+
+    // Moves past the instruction the CPU stopped at, which may be a breakpoint
+    fn resume(&mut self, gameboy: &mut Gameboy) -> Resume {
+        if let Some(stop) = gameboy.debug_step() {
+            return self.prompt(gameboy, Some(stop));
+        }
+        Resume::Run
+    }
+
+    fn disassemble_around(&self, gameboy: &Gameboy, addr: u16) {
+        let mut addrs = instructions_before(gameboy, addr);
+        let mut next = addr;
+        for _ in 0..=DISASM_AFTER {
+            addrs.push(next);
+            next = next.wrapping_add(gameboy.disassemble(next).1);
+        }
+        for addr in addrs {
+            print_instruction(gameboy, addr, &self.breakpoints);
+        }
+    }
+}
+
+impl DebugFrontend for Debugger {
+    fn run_frame(&mut self, gameboy: &mut Gameboy, video: &mut dyn VideoSink) -> bool {
+        if self.pause {
+            self.pause = false;
+            video.present(gameboy.framebuffer());
+            if let Resume::Quit = self.prompt(gameboy, None) {
+                return false;
+            }
+        }
+        if !self.armed() {
+            gameboy.run_frame();
+            return true;
+        }
+        while let Some(stop) = gameboy.run_frame_debug() {
+            video.present(gameboy.framebuffer());
+            if let Resume::Quit = self.prompt(gameboy, Some(stop)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn pause(&mut self) -> Result<(), String> {
+        if self.detached {
+            return Err("The debugger has no input".into());
+        }
+        self.pause = true;
+        Ok(())
+    }
+}
+
+/// Drives the Game Boy like `Gameboy::run`, with F12 and the debugger's stops breaking into it.
+/// With a `link_cable` to another emulator the past can't be changed on one side only, so
+/// loading states and rewinding are refused
+pub fn run(
+    gameboy: &mut Gameboy,
+    video: &mut dyn VideoSink,
+    audio: &mut dyn AudioSink,
+    input: &mut dyn InputSource,
+    debugger: &mut dyn DebugFrontend,
+    link_cable: bool,
+) {
+    gameboy.set_capture_channels(audio.wants_channel_samples());
+    let mut warned = false;
+    loop {
+        let state = input.poll();
+        if state.quit {
+            break;
+        }
+        gameboy.set_sample_rate(audio.sample_rate());
+        gameboy.set_buttons(state.buttons);
+        let result = match state.hotkey {
+            Some(Hotkey::SaveState(slot)) => gameboy.save_state_to_slot(slot),
+            Some(Hotkey::LoadState(_)) if link_cable => Err(LINK_CABLE_MESSAGE.into()),
+            Some(Hotkey::LoadState(slot)) => gameboy.load_state_from_slot(slot),
+            Some(Hotkey::Debug) => debugger.pause(),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            video.show_message(&e);
+        }
+        let rewind = state.rewind && !link_cable;
+        if state.rewind && link_cable && !warned {
+            warned = true;
+            video.show_message(LINK_CABLE_MESSAGE);
+        }
+
+        if !(rewind && gameboy.rewind_frame()) && !debugger.run_frame(gameboy, video) {
+            break;
+        }
+        if let Some(e) = gameboy.take_save_error() {
+            video.show_message(&e);
+        }
+
+        audio.queue_samples(gameboy.audio_samples());
+        audio.queue_channel_samples(gameboy.channel_samples());
+        gameboy.clear_audio_samples();
+        video.present(gameboy.framebuffer());
+    }
+
+    if let Err(e) = gameboy.flush_save() {
+        video.show_message(&e);
+    }
+}
+
+/// The GDB stub for `--gdb`, waiting for the client to connect, otherwise the REPL, which starts
+/// paused with `--debug`
+pub fn open_debugger(args: &Args) -> Result<Box<dyn DebugFrontend>, String> {
+    match args.gdb {
+        Some(port) => Ok(Box::new(GdbStub::listen(port)?)),
+        None => Ok(Box::new(Debugger::new(args.debug))),
+    }
+}
+
+// CALL, conditional CALLs and RST push a return address
+fn is_call(opcode: u8) -> bool {
+    matches!(opcode, 0xCD | 0xC4 | 0xCC | 0xD4 | 0xDC) || opcode & 0xC7 == 0xC7
+}
+
+/// Start of the instructions leading up to `addr`. Code can't be decoded backwards, so this
+/// takes the furthest starting point whose instructions line up with `addr`
+fn instructions_before(gameboy: &Gameboy, addr: u16) -> Vec<u16> {
+    let max_back = (DISASM_BEFORE as u16 * MAX_INSTRUCTION_SIZE).min(addr);
+    for back in (1..=max_back).rev() {
+        let mut addrs = Vec::new();
+        let mut offset = back;
+        while offset > 0 {
+            let start = addr.wrapping_sub(offset);
+            let (_, size) = gameboy.disassemble(start);
+            if size > offset {
+                break;
+            }
+            addrs.push(start);
+            offset -= size;
+        }
+        if offset == 0 {
+            let skip = addrs.len().saturating_sub(DISASM_BEFORE);
+            return addrs.split_off(skip);
+        }
+    }
+    Vec::new()
+}
+
+fn print_instruction(gameboy: &Gameboy, addr: u16, breakpoints: &[Breakpoint]) {
+    let (text, size) = gameboy.disassemble(addr);
+    let bytes: Vec<String> = (0..size)
+        .map(|i| format!("{:02X}", gameboy.peek(addr.wrapping_add(i))))
+        .collect();
+    let pc = if addr == gameboy.registers().pc {
+        '>'
+    } else {
+        ' '
+    };
+    let rom_bank = gameboy.rom_bank();
+    let breakpoint = if breakpoints.iter().any(|b| b.matches(addr, rom_bank)) {
+        '*'
+    } else {
+        ' '
+    };
+    println!(
+        "{}{}{:04X}  {:<9} {}",
+        pc,
+        breakpoint,
+        addr,
+        bytes.join(" "),
+        text
+    );
+}
+
+fn hexdump(gameboy: &Gameboy, addr: u16, len: u16) {
+    for row in (0..len).step_by(HEXDUMP_WIDTH as usize) {
+        let row_addr = addr.wrapping_add(row);
+        let bytes: Vec<u8> = (0..HEXDUMP_WIDTH.min(len - row))
+            .map(|i| gameboy.peek(row_addr.wrapping_add(i)))
+            .collect();
+        let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+        let text: String = bytes
+            .iter()
+            .map(|&byte| match byte {
+                0x20..=0x7E => byte as char,
+                _ => '.',
+            })
+            .collect();
+        println!(
+            "{:04X}  {:<width$}  {}",
+            row_addr,
+            hex.join(" "),
+            text,
+            width = HEXDUMP_WIDTH as usize * 3 - 1
+        );
+    }
+}
+
+fn parse_addr(text: &str) -> Result<u16, String> {
+    let digits = text
+        .strip_prefix('$')
+        .or_else(|| text.strip_prefix("0x"))
+        .unwrap_or(text);
+    u16::from_str_radix(digits, 16).map_err(|_| format!("Invalid address: {}", text))
+}
+
+/// `addr` or `bank:addr`
+fn parse_breakpoint(text: &str) -> Result<Breakpoint, String> {
+    match text.split_once(':') {
+        Some((bank, addr)) => Ok(Breakpoint::in_bank(parse_addr(bank)?, parse_addr(addr)?)),
+        None => Ok(Breakpoint::new(parse_addr(text)?)),
+    }
+}
+
+/// `addr`, `start-end` or `io`
+fn parse_range(text: &str) -> Result<(u16, u16), String> {
+    if text == "io" {
+        return Ok((IO_BEGIN, IO_END));
+    }
+    let (start, end) = match text.split_once('-') {
+        Some((start, end)) => (parse_addr(start)?, parse_addr(end)?),
+        None => (parse_addr(text)?, parse_addr(text)?),
+    };
+    if end < start {
+        return Err(format!("Empty range: {}", text));
+    }
+    Ok((start, end))
+}
+
+fn parse_watchpoint(kind: &str, range: &str) -> Result<Watchpoint, String> {
+    let kind = match kind {
+        "r" => WatchKind::Read,
+        "w" => WatchKind::Write,
+        "rw" => WatchKind::Access,
+        _ => return Err(format!("Watch r, w or rw, not {}", kind)),
+    };
+    let (start, end) = parse_range(range)?;
+    Ok(Watchpoint { kind, start, end })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frontend::debugger::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_breakpoint("$150"), Ok(Breakpoint::new(0x0150)));
+        assert_eq!(
+            parse_breakpoint("2:4000"),
+            Ok(Breakpoint::in_bank(2, 0x4000))
+        );
+        assert!(parse_breakpoint("g000").is_err());
+        assert_eq!(parse_range("io"), Ok((IO_BEGIN, IO_END)));
+        assert_eq!(parse_range("0xC000-C0FF"), Ok((0xC000, 0xC0FF)));
+        assert!(parse_range("C0FF-C000").is_err());
+        assert!(parse_watchpoint("x", "C000").is_err());
+        assert!(is_call(0xCD) && is_call(0xFF) && is_call(0xC7) && !is_call(0xC9));
+    }
+}
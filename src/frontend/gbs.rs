@@ -0,0 +1,52 @@
+use crate::arg_parse::args::Args;
+use crate::frontend::wav::{RECORDING_SAMPLE_RATE, WavSink};
+use rustemu::{AudioSink, GbsPlayer};
+use std::path::Path;
+
+const DEFAULT_TRACK_SECONDS: u64 = 150;
+// 59.73 frames per second, rounded up so a track is never cut short
+const FRAMES_PER_SECOND: u64 = 60;
+
+/// `<base>-03.wav` for track 3, unless a single track goes to the `--record-audio` file
+fn track_path(gbs_file: &str, args: &Args, track: u8, track_count: usize) -> String {
+    match args.record_audio.as_deref() {
+        Some(path) if track_count == 1 => path.into(),
+        path => {
+            let base = Path::new(path.unwrap_or(gbs_file)).with_extension("");
+            format!("{}-{:02}.wav", base.display(), track)
+        }
+    }
+}
+
+/// Renders the selected tracks of a GBS file to WAV files, every track when none are selected
+pub fn render_gbs(gbs_file: &str, args: &Args) -> Result<(), String> {
+    let data = rustemu::read_file(gbs_file).map_err(|e| e.to_string())?;
+    let mut player = GbsPlayer::new(&data)?;
+    println!("{}", player.header());
+
+    let tracks = if args.tracks.is_empty() {
+        (1..=player.header().song_count).collect()
+    } else {
+        args.tracks.clone()
+    };
+    let frames = args.seconds.unwrap_or(DEFAULT_TRACK_SECONDS) * FRAMES_PER_SECOND;
+
+    player.set_sample_rate(RECORDING_SAMPLE_RATE);
+    player.set_capture_channels(args.record_stems);
+    for &track in &tracks {
+        player.start_track(track)?;
+
+        let path = track_path(gbs_file, args, track, tracks.len());
+        let mut recording = WavSink::create(&path, args.record_stems, RECORDING_SAMPLE_RATE)?;
+        for _ in 0..frames {
+            player.run_frame();
+            recording.queue_samples(player.audio_samples());
+            recording.queue_channel_samples(player.channel_samples());
+            player.clear_audio_samples();
+        }
+        recording.finish()?;
+        println!("Track {} -> {}", track, path);
+    }
+
+    Ok(())
+}
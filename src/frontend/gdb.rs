@@ -0,0 +1,440 @@
+use crate::frontend::debugger::DebugFrontend;
+use rustemu::{Breakpoint, DebugBreak, Gameboy, Registers, VideoSink, WatchKind, Watchpoint};
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+
+// Sent by the client to stop a running target
+const INTERRUPT: u8 = 0x03;
+const SIGINT: u8 = 2;
+const SIGTRAP: u8 = 5;
+// AF, BC, DE, HL, SP and PC, the first registers of gdb's Z80 layout
+const REGISTER_COUNT: usize = 6;
+const PACKET_SIZE: usize = 0x1000;
+
+/// What the client asked for once it is done with a stopped target
+enum Resume {
+    Run,
+    Detach,
+    Kill,
+}
+
+/// GDB remote serial protocol server on a local port. The client drives the emulation, the
+/// window only runs while it says continue
+pub struct GdbStub {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    breakpoints: Vec<Breakpoint>,
+    watchpoints: Vec<Watchpoint>,
+    // The client said continue and waits for a stop reply
+    running: bool,
+    pause: bool,
+    detached: bool,
+}
+
+impl GdbStub {
+    /// Waits for a GDB client, the emulation starts stopped until it says continue
+    pub fn listen(port: u16) -> Result<Self, String> {
+        let listener = TcpListener::bind(("127.0.0.1", port))
+            .map_err(|e| format!("Failed to listen on port {}: {}", port, e))?;
+        println!("Waiting for GDB on port {}", port);
+        let (stream, peer) = listener.accept().map_err(|e| e.to_string())?;
+        println!("GDB connected from {}", peer);
+        Self::from_stream(stream).map_err(|e| e.to_string())
+    }
+
+    fn from_stream(stream: TcpStream) -> io::Result<Self> {
+        stream.set_nodelay(true)?;
+        Ok(Self {
+            reader: BufReader::new(stream.try_clone()?),
+            writer: stream,
+            breakpoints: Vec::new(),
+            watchpoints: Vec::new(),
+            running: false,
+            pause: false,
+            detached: false,
+        })
+    }
+
+    /// Next packet with a good checksum, acknowledging it. `None` once the client is gone
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            let mut skipped = Vec::new();
+            // Acks and interrupts of a stopped target are dropped
+            if self.reader.read_until(b'$', &mut skipped)? == 0 || skipped.last() != Some(&b'$') {
+                return Ok(None);
+            }
+            let mut data = Vec::new();
+            self.reader.read_until(b'#', &mut data)?;
+            if data.pop() != Some(b'#') {
+                return Ok(None);
+            }
+            let mut checksum = [0u8; 2];
+            self.reader.read_exact(&mut checksum)?;
+            let expected = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+            if expected != Some(checksum_of(&data)) {
+                self.writer.write_all(b"-")?;
+                continue;
+            }
+            self.writer.write_all(b"+")?;
+            return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+        }
+    }
+
+    fn send(&mut self, data: &str) -> io::Result<()> {
+        let packet = format!("${}#{:02x}", data, checksum_of(data.as_bytes()));
+        self.writer.write_all(packet.as_bytes())
+    }
+
+    /// Checks for an interrupt from the client without waiting
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.reader.get_ref().set_nonblocking(true)?;
+        let result = match self.reader.fill_buf() {
+            Ok([]) => Err(ErrorKind::UnexpectedEof.into()),
+            Ok(bytes) => {
+                let interrupted = bytes.contains(&INTERRUPT);
+                let len = bytes.len();
+                self.reader.consume(len);
+                Ok(interrupted)
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(e) => Err(e),
+        };
+        self.reader.get_ref().set_nonblocking(false)?;
+        result
+    }
+
+    /// Reports why the target stopped and answers packets until the client resumes it
+    fn serve(&mut self, gameboy: &mut Gameboy, stop: Option<String>) -> io::Result<Resume> {
+        if let Some(stop) = stop {
+            self.running = false;
+            self.send(&stop)?;
+        }
+        loop {
+            let Some(packet) = self.read_packet()? else {
+                return Ok(Resume::Detach);
+            };
+            let reply = match packet.as_bytes().first() {
+                Some(b'?') => stop_reply(None),
+                Some(b'g') => encode_registers(&gameboy.registers()),
+                Some(b'G') => match decode_registers(&packet[1..], gameboy.registers()) {
+                    Some(registers) => {
+                        gameboy.set_registers(registers);
+                        "OK".into()
+                    }
+                    None => "E01".into(),
+                },
+                Some(b'p') => match usize::from_str_radix(&packet[1..], 16) {
+                    Ok(index) if index < REGISTER_COUNT => {
+                        hex(&register_pairs(&gameboy.registers())[index].to_le_bytes())
+                    }
+                    _ => "E01".into(),
+                },
+                Some(b'P') => write_register(gameboy, &packet[1..]),
+                Some(b'm') => read_memory(gameboy, &packet[1..]),
+                Some(b'M') => write_memory(gameboy, &packet[1..]),
+                Some(b'Z') => self.set_stop_point(gameboy, &packet[1..], true),
+                Some(b'z') => self.set_stop_point(gameboy, &packet[1..], false),
+                Some(b's') => stop_reply(gameboy.debug_step()),
+                Some(b'c') => {
+                    // Moves past a breakpoint at the PC first
+                    if let Some(stop) = gameboy.debug_step() {
+                        stop_reply(Some(stop))
+                    } else {
+                        self.running = true;
+                        return Ok(Resume::Run);
+                    }
+                }
+                Some(b'D') => {
+                    self.send("OK")?;
+                    return Ok(Resume::Detach);
+                }
+                Some(b'k') => return Ok(Resume::Kill),
+                Some(b'H') => "OK".into(),
+                _ if packet.starts_with("qSupported") => format!("PacketSize={:x}", PACKET_SIZE),
+                _ if packet == "qAttached" => "1".into(),
+                // Everything else is unsupported, which the client is told with an empty reply
+                _ => String::new(),
+            };
+            self.send(&reply)?;
+        }
+    }
+
+    /// `Z`/`z` packets, software and hardware breakpoints plus write, read and access
+    /// watchpoints
+    fn set_stop_point(&mut self, gameboy: &mut Gameboy, args: &str, insert: bool) -> String {
+        let mut fields = args.split(',');
+        let (Some(kind), Some(Ok(addr)), Some(Ok(len))) = (
+            fields.next(),
+            fields.next().map(|addr| u16::from_str_radix(addr, 16)),
+            fields.next().map(|len| u16::from_str_radix(len, 16)),
+        ) else {
+            return "E01".into();
+        };
+        let watch_kind = match kind {
+            "0" | "1" => {
+                let breakpoint = Breakpoint::new(addr);
+                self.breakpoints.retain(|&b| b != breakpoint);
+                if insert {
+                    self.breakpoints.push(breakpoint);
+                }
+                gameboy.set_breakpoints(&self.breakpoints);
+                return "OK".into();
+            }
+            "2" => WatchKind::Write,
+            "3" => WatchKind::Read,
+            "4" => WatchKind::Access,
+            _ => return String::new(),
+        };
+        let watchpoint = Watchpoint {
+            kind: watch_kind,
+            start: addr,
+            end: addr.saturating_add(len.max(1) - 1),
+        };
+        self.watchpoints.retain(|&w| w != watchpoint);
+        if insert {
+            self.watchpoints.push(watchpoint);
+        }
+        gameboy.set_watchpoints(&self.watchpoints);
+        "OK".into()
+    }
+
+    // The client went away, the game carries on without stops
+    fn detach(&mut self, gameboy: &mut Gameboy) {
+        println!("GDB detached");
+        self.detached = true;
+        let _ = self.reader.get_ref().shutdown(Shutdown::Both);
+        self.breakpoints.clear();
+        self.watchpoints.clear();
+        gameboy.set_breakpoints(&[]);
+        gameboy.set_watchpoints(&[]);
+        self.running = true;
+    }
+
+    /// Serves the client until it resumes, false once it killed the target
+    fn stop(&mut self, gameboy: &mut Gameboy, stop: Option<String>) -> bool {
+        match self.serve(gameboy, stop) {
+            Ok(Resume::Run) => true,
+            Ok(Resume::Kill) => false,
+            Ok(Resume::Detach) | Err(_) => {
+                self.detach(gameboy);
+                true
+            }
+        }
+    }
+}
+
+// A client still waiting for a stop learns that the emulator closed
+impl Drop for GdbStub {
+    fn drop(&mut self) {
+        if self.running && !self.detached {
+            let _ = self.send("W00");
+        }
+    }
+}
+
+impl DebugFrontend for GdbStub {
+    fn run_frame(&mut self, gameboy: &mut Gameboy, video: &mut dyn VideoSink) -> bool {
+        if self.detached {
+            gameboy.run_frame();
+            return true;
+        }
+
+        let interrupted = self.running
+            && self.interrupted().unwrap_or_else(|_| {
+                self.detach(gameboy);
+                false
+            });
+        let pause = std::mem::take(&mut self.pause);
+        if !self.detached && (interrupted || pause || !self.running) {
+            // Until the first continue the client is still asking questions, not waiting for a stop
+            let stop = self.running.then(|| stop_reply_signal(SIGINT));
+            video.present(gameboy.framebuffer());
+            if !self.stop(gameboy, stop) {
+                return false;
+            }
+        }
+
+        while let Some(stop) = gameboy.run_frame_debug() {
+            video.present(gameboy.framebuffer());
+            if !self.stop(gameboy, Some(stop_reply(Some(stop)))) {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn pause(&mut self) -> Result<(), String> {
+        if self.detached {
+            return Err("GDB is not connected".into());
+        }
+        self.pause = true;
+        Ok(())
+    }
+}
+
+fn checksum_of(data: &[u8]) -> u8 {
+    data.iter().fold(0u8, |sum, &byte| sum.wrapping_add(byte))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn unhex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn stop_reply_signal(signal: u8) -> String {
+    format!("S{:02x}", signal)
+}
+
+fn stop_reply(stop: Option<DebugBreak>) -> String {
+    match stop {
+        Some(DebugBreak::Watchpoint(hit)) => {
+            let kind = if hit.write { "watch" } else { "rwatch" };
+            format!("T{:02x}{}:{:04x};", SIGTRAP, kind, hit.addr)
+        }
+        _ => stop_reply_signal(SIGTRAP),
+    }
+}
+
+fn register_pairs(registers: &Registers) -> [u16; REGISTER_COUNT] {
+    let pair = |high: u8, low: u8| u16::from_be_bytes([high, low]);
+    [
+        pair(registers.a, registers.f),
+        pair(registers.b, registers.c),
+        pair(registers.d, registers.e),
+        pair(registers.h, registers.l),
+        registers.sp,
+        registers.pc,
+    ]
+}
+
+/// Takes IME and HALT from `current`, GDB does not know about them
+fn registers_from_pairs(pairs: [u16; REGISTER_COUNT], current: Registers) -> Registers {
+    let [[a, f], [b, c], [d, e], [h, l]] = [0, 1, 2, 3].map(|i| pairs[i].to_be_bytes());
+    Registers {
+        a,
+        f,
+        b,
+        c,
+        d,
+        e,
+        h,
+        l,
+        sp: pairs[4],
+        pc: pairs[5],
+        ..current
+    }
+}
+
+// Registers are sent as little endian 16-bit values
+fn encode_registers(registers: &Registers) -> String {
+    register_pairs(registers)
+        .iter()
+        .map(|pair| hex(&pair.to_le_bytes()))
+        .collect()
+}
+
+fn decode_registers(text: &str, current: Registers) -> Option<Registers> {
+    let bytes = unhex(text)?;
+    if bytes.len() < REGISTER_COUNT * 2 {
+        return None;
+    }
+    let mut pairs = [0u16; REGISTER_COUNT];
+    for (pair, bytes) in pairs.iter_mut().zip(bytes.as_chunks::<2>().0) {
+        *pair = u16::from_le_bytes(*bytes);
+    }
+    Some(registers_from_pairs(pairs, current))
+}
+
+fn parse_addr_len(text: &str) -> Option<(u16, usize)> {
+    let (addr, len) = text.split_once(',')?;
+    Some((
+        u16::from_str_radix(addr, 16).ok()?,
+        usize::from_str_radix(len, 16).ok()?,
+    ))
+}
+
+/// `P n=value`
+fn write_register(gameboy: &mut Gameboy, args: &str) -> String {
+    let parsed = args.split_once('=').and_then(|(index, value)| {
+        let index = usize::from_str_radix(index, 16).ok()?;
+        let bytes = unhex(value)?;
+        (index < REGISTER_COUNT && bytes.len() == 2)
+            .then(|| (index, u16::from_le_bytes([bytes[0], bytes[1]])))
+    });
+    let Some((index, value)) = parsed else {
+        return "E01".into();
+    };
+    let mut pairs = register_pairs(&gameboy.registers());
+    pairs[index] = value;
+    gameboy.set_registers(registers_from_pairs(pairs, gameboy.registers()));
+    "OK".into()
+}
+
+/// `m addr,len`
+fn read_memory(gameboy: &Gameboy, args: &str) -> String {
+    let Some((addr, len)) = parse_addr_len(args) else {
+        return "E01".into();
+    };
+    let bytes: Vec<u8> = (0..len.min(PACKET_SIZE / 2))
+        .map(|i| gameboy.peek(addr.wrapping_add(i as u16)))
+        .collect();
+    hex(&bytes)
+}
+
+/// `M addr,len:data`, ROM writes reach the banking registers like the CPU's would
+fn write_memory(gameboy: &mut Gameboy, args: &str) -> String {
+    let parsed = args.split_once(':').and_then(|(addr_len, data)| {
+        let (addr, len) = parse_addr_len(addr_len)?;
+        let bytes = unhex(data)?;
+        (bytes.len() == len).then_some((addr, bytes))
+    });
+    let Some((addr, bytes)) = parsed else {
+        return "E01".into();
+    };
+    for (i, &byte) in bytes.iter().enumerate() {
+        gameboy.poke(addr.wrapping_add(i as u16), byte);
+    }
+    "OK".into()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frontend::gdb::*;
+
+    #[test]
+    fn test_registers() {
+        let registers = Registers {
+            a: 0x01,
+            f: 0xB0,
+            b: 0x02,
+            c: 0x03,
+            sp: 0xFFFE,
+            pc: 0x0150,
+            ime: true,
+            ..Registers::default()
+        };
+        let encoded = encode_registers(&registers);
+        assert_eq!(encoded, "b001030200000000feff5001");
+        assert_eq!(
+            decode_registers(&encoded, Registers::default()),
+            Some(Registers {
+                ime: false,
+                ..registers
+            })
+        );
+        assert_eq!(checksum_of(b"OK"), 0x9A);
+        assert_eq!(unhex("0aFF"), Some(vec![0x0A, 0xFF]));
+        assert_eq!(unhex("0"), None);
+    }
+}
@@ -0,0 +1,130 @@
+use crate::frontend::FRAME_DURATION;
+use crate::frontend::palette::Palette;
+use rustemu::{
+    Button, InputSource, InputState, PixelLevel, SCREEN_HEIGHT, SCREEN_WIDTH, VideoSink,
+};
+use uefi::boot;
+
+/// Draws straight into the GOP framebuffer, centered on the screen
+pub struct GopVideo {
+    palette: Palette,
+    fb_base: *mut u8,
+    fb_stride: usize,
+    width: usize,
+    height: usize,
+}
+
+impl GopVideo {
+    pub fn new(palette: Palette) -> Self {
+        use uefi::boot::{OpenProtocolAttributes, OpenProtocolParams};
+        use uefi::proto::console::gop::GraphicsOutput;
+
+        log::info!("Initializing GUI");
+        let gop_handle = boot::get_handle_for_protocol::<GraphicsOutput>().unwrap();
+
+        let mut gop = unsafe {
+            boot::open_protocol::<GraphicsOutput>(
+                OpenProtocolParams {
+                    handle: gop_handle,
+                    agent: boot::image_handle(),
+                    controller: None,
+                },
+                OpenProtocolAttributes::GetProtocol,
+            )
+            .unwrap()
+        };
+
+        let (width, height) = gop.current_mode_info().resolution();
+        let stride = gop.current_mode_info().stride() * 4;
+        let fb_base = gop.frame_buffer().as_mut_ptr();
+
+        Self {
+            palette,
+            fb_base,
+            fb_stride: stride,
+            width,
+            height,
+        }
+    }
+}
+
+impl VideoSink for GopVideo {
+    fn present(&mut self, frame: &[PixelLevel; SCREEN_WIDTH * SCREEN_HEIGHT]) {
+        let offset_x = (self.width - SCREEN_WIDTH) / 2;
+        let offset_y = (self.height - SCREEN_HEIGHT) / 2;
+
+        let fb = self.fb_base as *mut u32;
+        let stride = self.fb_stride / 4;
+
+        for y in 0..SCREEN_HEIGHT {
+            for x in 0..SCREEN_WIDTH {
+                let pixel_value = self.palette.translate_palette(frame[y * SCREEN_WIDTH + x]);
+                unsafe {
+                    *fb.add((offset_y + y) * stride + (offset_x + x)) = pixel_value;
+                }
+            }
+        }
+
+        boot::stall(FRAME_DURATION.as_micros() as usize);
+    }
+
+    fn show_message(&mut self, message: &str) {
+        log::info!("{}", message);
+    }
+}
+
+/// Keyboard of the UEFI text console. It only reports key presses, so a button counts as
+/// pressed for the frame its key event arrives in
+pub struct ConsoleInput;
+
+impl InputSource for ConsoleInput {
+    fn poll(&mut self) -> InputState {
+        use uefi::proto::console::text::*;
+
+        let mut state = InputState::default();
+
+        let handle = boot::get_handle_for_protocol::<Input>();
+        if handle.is_err() {
+            return state;
+        }
+
+        let input = boot::open_protocol_exclusive::<Input>(handle.unwrap());
+        if input.is_err() {
+            return state;
+        }
+
+        let mut input = input.unwrap();
+
+        loop {
+            let key = input.read_key();
+            if key.is_err() {
+                break;
+            }
+            let key = key.unwrap();
+            if key.is_none() {
+                break;
+            }
+            let key = key.unwrap();
+
+            let button = match key {
+                Key::Special(ScanCode::UP) => Some(Button::Up),
+                Key::Special(ScanCode::DOWN) => Some(Button::Down),
+                Key::Special(ScanCode::LEFT) => Some(Button::Left),
+                Key::Special(ScanCode::RIGHT) => Some(Button::Right),
+                Key::Printable(c) => match u16::from(c) as u8 as char {
+                    'a' | 'A' => Some(Button::A),
+                    'b' | 'B' => Some(Button::B),
+                    'e' | 'E' => Some(Button::Select),
+                    ' ' => Some(Button::Start),
+                    _ => None,
+                },
+                _ => None,
+            };
+            if let Some(button) = button {
+                state.buttons |= button as u8;
+            }
+        }
+
+        state
+    }
+}
@@ -0,0 +1,201 @@
+use crate::arg_parse::args::Args;
+use crate::frontend::debugger::{self, open_debugger};
+use crate::frontend::link::open_link;
+use crate::frontend::linked::load_second;
+use crate::frontend::printer::{self, PrinterOutput};
+use crate::frontend::serial::{SerialMonitor, TestResult};
+use crate::frontend::wav::{RECORDING_SAMPLE_RATE, WavSink};
+use rustemu::{
+    AudioSink, Gameboy, InputSource, InputState, LinkedGameboys, NullAudio, PixelLevel,
+    SCREEN_HEIGHT, SCREEN_WIDTH, VideoSink,
+};
+use std::fs;
+use std::path::Path;
+
+const DEFAULT_FRAMEBUFFER_PATH: &str = "framebuffer.pgm";
+const DEFAULT_MEMORY_PATH: &str = "memory.bin";
+
+// Shades for pixel levels 0-3, lightest to darkest like the default palette
+const PGM_SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+/// Quits after a fixed number of frames without pressing any buttons
+struct FrameLimit {
+    frames_left: u64,
+}
+
+impl InputSource for FrameLimit {
+    fn poll(&mut self) -> InputState {
+        let quit = self.frames_left == 0;
+        self.frames_left = self.frames_left.saturating_sub(1);
+        InputState {
+            quit,
+            ..InputState::default()
+        }
+    }
+}
+
+/// Discards the frames, only the last one is dumped once the run is over
+struct NullVideo;
+
+impl VideoSink for NullVideo {
+    fn present(&mut self, _frame: &[PixelLevel; SCREEN_WIDTH * SCREEN_HEIGHT]) {}
+
+    fn show_message(&mut self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+/// Binary graymap of the framebuffers side by side, readable by most image tools and trivial
+/// to parse
+fn framebuffer_pgm(gameboys: &[&Gameboy]) -> Vec<u8> {
+    let width = SCREEN_WIDTH * gameboys.len();
+    let mut pgm = format!("P5\n{} {}\n255\n", width, SCREEN_HEIGHT).into_bytes();
+    for row in 0..SCREEN_HEIGHT {
+        for gameboy in gameboys {
+            let line = &gameboy.framebuffer()[row * SCREEN_WIDTH..(row + 1) * SCREEN_WIDTH];
+            pgm.extend(line.iter().map(|&pixel| PGM_SHADES[pixel as usize]));
+        }
+    }
+    pgm
+}
+
+/// Writes the framebuffers and the memory of each Game Boy, the second one's memory goes to
+/// `<name>-2.<ext>`
+fn write_dumps(gameboys: &[&Gameboy], args: &Args) -> Result<(), String> {
+    let framebuffer_path = args
+        .dump_framebuffer
+        .as_deref()
+        .unwrap_or(DEFAULT_FRAMEBUFFER_PATH);
+    fs::write(framebuffer_path, framebuffer_pgm(gameboys))
+        .map_err(|e| format!("Failed to write {}: {}", framebuffer_path, e))?;
+
+    let memory_path = args.dump_memory.as_deref().unwrap_or(DEFAULT_MEMORY_PATH);
+    for (index, gameboy) in gameboys.iter().enumerate() {
+        let path = match index {
+            0 => memory_path.into(),
+            _ => player_path(memory_path, index + 1),
+        };
+        fs::write(&path, gameboy.memory_dump())
+            .map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+    Ok(())
+}
+
+fn player_path(path: &str, player: usize) -> String {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, player, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, player),
+    };
+    path.with_file_name(name).display().to_string()
+}
+
+/// Runs the ROM for the requested frames, then cycles, without a window and dumps the final
+/// framebuffer and memory. Returns what a test ROM reported over serial
+pub fn run_headless(rom_file: &str, args: &Args) -> Result<Option<TestResult>, String> {
+    if args.frames.is_none() && args.cycles.is_none() {
+        return Err("--headless needs --frames or --cycles".into());
+    }
+
+    let mut gameboy = Gameboy::new();
+    let header = gameboy.load(rom_file).map_err(|e| e.to_string())?;
+    crate::warn_header_checksums(&header);
+    if let Some(link_rom) = args.link_rom.as_deref() {
+        let pair = LinkedGameboys::new(gameboy, load_second(rom_file, link_rom)?);
+        return run_linked(pair, args).map(|()| None);
+    }
+    if let Some(link) = open_link(args)? {
+        gameboy.set_serial_device(link);
+    }
+
+    let input = FrameLimit {
+        frames_left: args.frames.unwrap_or(0),
+    };
+    let input = SerialMonitor::new(&mut gameboy, input, args.serial_stdout, args.test_result);
+    if let Some(dir) = args.printer.as_deref() {
+        printer::create_dir(dir)?;
+    }
+    let mut input = PrinterOutput::new(&mut gameboy, input, args.printer.as_deref());
+    let mut recording = args
+        .record_audio
+        .as_deref()
+        .map(|path| WavSink::create(path, args.record_stems, RECORDING_SAMPLE_RATE))
+        .transpose()?;
+    let mut null_audio = NullAudio;
+    let audio: &mut dyn AudioSink = match recording.as_mut() {
+        Some(recording) => recording,
+        None => &mut null_audio,
+    };
+
+    if args.debug || args.gdb.is_some() {
+        let mut debugger = open_debugger(args)?;
+        debugger::run(
+            &mut gameboy,
+            &mut NullVideo,
+            audio,
+            &mut input,
+            debugger.as_mut(),
+            args.link_listen.is_some() || args.link_connect.is_some(),
+        );
+    } else {
+        gameboy.run(&mut NullVideo, audio, &mut input);
+    }
+    // Cycles after a reported result are skipped like the remaining frames
+    let test_result = input.input_mut().finish();
+    if !(args.test_result && test_result.is_some()) {
+        gameboy.run_cycles(args.cycles.unwrap_or(0));
+    }
+    // Samples from the cycles after the last frame
+    audio.queue_samples(gameboy.audio_samples());
+    audio.queue_channel_samples(gameboy.channel_samples());
+    gameboy.clear_audio_samples();
+    let save_result = gameboy.flush_save();
+
+    if let Some(recording) = recording {
+        recording.finish()?;
+    }
+    save_result?;
+
+    input.finish()?;
+    write_dumps(&[&gameboy], args)?;
+    Ok(input.input_mut().finish())
+}
+
+/// Runs a linked pair like `run_headless` runs a single Game Boy, the first one is recorded.
+/// Without input from outside the outcome only depends on the ROMs and their saves
+fn run_linked(mut pair: LinkedGameboys, args: &Args) -> Result<(), String> {
+    let mut recording = args
+        .record_audio
+        .as_deref()
+        .map(|path| WavSink::create(path, args.record_stems, RECORDING_SAMPLE_RATE))
+        .transpose()?;
+    let first = &mut pair.gameboys_mut()[0];
+    first.set_sample_rate(RECORDING_SAMPLE_RATE);
+    first.set_capture_channels(args.record_stems);
+
+    let mut collect_samples = |pair: &mut LinkedGameboys| {
+        let [first, second] = pair.gameboys_mut();
+        if let Some(recording) = recording.as_mut() {
+            recording.queue_samples(first.audio_samples());
+            recording.queue_channel_samples(first.channel_samples());
+        }
+        first.clear_audio_samples();
+        second.clear_audio_samples();
+    };
+    for _ in 0..args.frames.unwrap_or(0) {
+        pair.run_frame();
+        collect_samples(&mut pair);
+    }
+    pair.run_cycles(args.cycles.unwrap_or(0));
+    collect_samples(&mut pair);
+    let save_result = pair.flush_saves();
+
+    if let Some(recording) = recording {
+        recording.finish()?;
+    }
+    save_result?;
+
+    let [first, second] = pair.gameboys();
+    write_dumps(&[first, second], args)
+}
@@ -0,0 +1,280 @@
+use crate::arg_parse::args::Args;
+use rustemu::SerialDevice;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+
+// Both sides swap their link port state every 4096 dots, the time a byte takes on the internal
+// clock, and settle transfers from the two messages alone. Each emulator sees the same outcome
+// at the same emulated time, however fast either of them runs
+const SYNC_DOTS: u32 = 4096;
+const HELLO: [u8; 4] = *b"RLNK";
+const PROTOCOL_VERSION: u8 = 1;
+
+const FLAG_SENDING: u8 = 0x01;
+const FLAG_LISTENING: u8 = 0x02;
+
+/// One side of the link port at a sync
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+struct PortState {
+    // SB of a transfer started on the internal clock, waiting for its answer
+    sending: Option<u8>,
+    // SB while waiting for the other side to clock a transfer
+    listening: Option<u8>,
+}
+
+impl PortState {
+    fn encode(self) -> [u8; 3] {
+        let mut flags = 0;
+        if self.sending.is_some() {
+            flags |= FLAG_SENDING;
+        }
+        if self.listening.is_some() {
+            flags |= FLAG_LISTENING;
+        }
+        [
+            flags,
+            self.sending.unwrap_or(0xFF),
+            self.listening.unwrap_or(0xFF),
+        ]
+    }
+
+    fn decode(message: [u8; 3]) -> Self {
+        Self {
+            sending: (message[0] & FLAG_SENDING != 0).then_some(message[1]),
+            listening: (message[0] & FLAG_LISTENING != 0).then_some(message[2]),
+        }
+    }
+}
+
+/// What `local` gets out of a sync: the answer to its own transfer, and the byte clocked in by
+/// the other side. A transfer nobody listens to reads the pulled up data line
+fn settle(local: PortState, remote: PortState) -> (Option<u8>, Option<u8>) {
+    let answer = local.sending.map(|_| match remote {
+        PortState {
+            sending: None,
+            listening: Some(byte),
+        } => byte,
+        _ => 0xFF,
+    });
+    let clocked_in = match local {
+        PortState {
+            sending: None,
+            listening: Some(_),
+        } => remote.sending,
+        _ => None,
+    };
+    (answer, clocked_in)
+}
+
+/// Link cable to another rustemu over TCP. Whichever Game Boy starts a transfer on its
+/// internal clock is the master, the other one has to be waiting on the external clock
+pub struct TcpLink {
+    // Gone once the other side hung up, the port then behaves like nothing is connected
+    stream: Option<TcpStream>,
+    dots: u32,
+    state: PortState,
+    answer: Option<u8>,
+    clocked_in: Option<u8>,
+}
+
+impl TcpLink {
+    /// Waits for the other side to connect on `addr`, see `listen_addr`
+    pub fn listen(addr: &str) -> Result<Self, String> {
+        let addr = listen_addr(addr);
+        let listener = TcpListener::bind(&addr)
+            .map_err(|e| format!("Failed to listen on {}: {}", addr, e))?;
+        println!("Waiting for the other Game Boy on {}", addr);
+        let (stream, peer) = listener.accept().map_err(|e| e.to_string())?;
+        println!("Linked with {}", peer);
+        Self::from_stream(stream)
+    }
+
+    pub fn connect(addr: &str) -> Result<Self, String> {
+        let stream = TcpStream::connect(addr)
+            .map_err(|e| format!("Failed to connect to {}: {}", addr, e))?;
+        println!("Linked with {}", addr);
+        Self::from_stream(stream)
+    }
+
+    fn from_stream(mut stream: TcpStream) -> Result<Self, String> {
+        let handshake = |stream: &mut TcpStream| -> io::Result<[u8; 5]> {
+            stream.set_nodelay(true)?;
+            let mut hello = [0u8; 5];
+            hello[..4].copy_from_slice(&HELLO);
+            hello[4] = PROTOCOL_VERSION;
+            stream.write_all(&hello)?;
+            stream.read_exact(&mut hello)?;
+            Ok(hello)
+        };
+        let hello = handshake(&mut stream).map_err(|e| format!("Link handshake failed: {}", e))?;
+        if hello[..4] != HELLO {
+            return Err("The other side is not a rustemu link cable".into());
+        }
+        if hello[4] != PROTOCOL_VERSION {
+            return Err(format!(
+                "The other side speaks link protocol {}, expected {}",
+                hello[4], PROTOCOL_VERSION
+            ));
+        }
+        Ok(Self {
+            stream: Some(stream),
+            dots: 0,
+            state: PortState::default(),
+            answer: None,
+            clocked_in: None,
+        })
+    }
+
+    fn exchange(stream: &mut TcpStream, local: PortState) -> io::Result<PortState> {
+        stream.write_all(&local.encode())?;
+        let mut message = [0u8; 3];
+        stream.read_exact(&mut message)?;
+        Ok(PortState::decode(message))
+    }
+
+    fn sync(&mut self) {
+        let local = self.state;
+        let remote = match self
+            .stream
+            .as_mut()
+            .map(|stream| Self::exchange(stream, local))
+        {
+            Some(Ok(remote)) => remote,
+            Some(Err(e)) => {
+                eprintln!("Link cable disconnected: {}", e);
+                self.stream = None;
+                PortState::default()
+            }
+            None => PortState::default(),
+        };
+        let (answer, clocked_in) = settle(local, remote);
+        self.answer = answer.or(self.answer);
+        self.clocked_in = clocked_in;
+        self.state.sending = None;
+    }
+}
+
+impl SerialDevice for TcpLink {
+    fn transfer(&mut self, byte: u8) -> Option<u8> {
+        self.answer = None;
+        if self.stream.is_none() {
+            return Some(0xFF);
+        }
+        self.state.sending = Some(byte);
+        None
+    }
+
+    fn poll_transfer(&mut self) -> Option<u8> {
+        self.answer.take()
+    }
+
+    fn external_transfer(&mut self, byte: u8) -> Option<u8> {
+        self.state.sending = None;
+        if let Some(byte) = self.clocked_in.take() {
+            return Some(byte);
+        }
+        self.state.listening = Some(byte);
+        None
+    }
+
+    fn tick(&mut self) {
+        // A clocked in byte is only there for the dot right after the sync, and the Game Boy
+        // is only still listening if it polls again after this dot
+        self.clocked_in = None;
+        self.dots += 1;
+        if self.dots == SYNC_DOTS {
+            self.dots = 0;
+            self.sync();
+        }
+        self.state.listening = None;
+    }
+}
+
+/// A bare port only accepts connections from this machine, other interfaces have to be named
+/// explicitly as `host:port`
+fn listen_addr(addr: &str) -> String {
+    match addr.parse::<u16>() {
+        Ok(port) => format!("127.0.0.1:{}", port),
+        Err(_) => addr.to_string(),
+    }
+}
+
+/// The link cable asked for on the command line, if any
+pub fn open_link(args: &Args) -> Result<Option<TcpLink>, String> {
+    match (args.link_listen.as_deref(), args.link_connect.as_deref()) {
+        (Some(addr), _) => TcpLink::listen(addr).map(Some),
+        (None, Some(addr)) => TcpLink::connect(addr).map(Some),
+        (None, None) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frontend::link::*;
+    use std::thread;
+
+    fn port(sending: Option<u8>, listening: Option<u8>) -> PortState {
+        PortState { sending, listening }
+    }
+
+    #[test]
+    fn test_settle() {
+        let master = port(Some(0x12), None);
+        let slave = port(None, Some(0x34));
+        assert_eq!(settle(master, slave), (Some(0x34), None));
+        assert_eq!(settle(slave, master), (None, Some(0x12)));
+
+        // Nobody listening, or both driving the clock
+        assert_eq!(settle(master, port(None, None)), (Some(0xFF), None));
+        assert_eq!(settle(master, master), (Some(0xFF), None));
+        assert_eq!(settle(slave, slave), (None, None));
+
+        for state in [master, slave, port(None, None)] {
+            assert_eq!(PortState::decode(state.encode()), state);
+        }
+    }
+
+    // Drives a link the way the serial port does, returning the byte that came in and the dot
+    fn run_transfer(mut link: TcpLink, sb: u8, internal_clock: bool) -> (u8, u32) {
+        let mut answer = if internal_clock {
+            link.transfer(sb)
+        } else {
+            None
+        };
+        for dot in 0..(4 * SYNC_DOTS) {
+            link.tick();
+            answer = match answer {
+                Some(_) => answer,
+                None if internal_clock => link.poll_transfer(),
+                None => link.external_transfer(sb),
+            };
+            if let Some(byte) = answer {
+                return (byte, dot);
+            }
+        }
+        panic!("transfer never finished");
+    }
+
+    #[test]
+    fn test_listen_addr() {
+        assert_eq!(listen_addr("5555"), "127.0.0.1:5555");
+        assert_eq!(listen_addr("0.0.0.0:5555"), "0.0.0.0:5555");
+    }
+
+    #[test]
+    fn test_transfer_over_localhost() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let slave = thread::spawn(move || {
+            let link = TcpLink::connect(&addr).unwrap();
+            run_transfer(link, 0x34, false)
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let master = run_transfer(TcpLink::from_stream(stream).unwrap(), 0x12, true);
+        let slave = slave.join().unwrap();
+
+        // Both sides see the transfer at the same sync
+        assert_eq!(master, (0x34, SYNC_DOTS - 1));
+        assert_eq!(slave, (0x12, SYNC_DOTS - 1));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::frontend::window::WindowVideo;
+use rustemu::{AudioSink, Gameboy, InputSource, LinkedGameboys};
+use std::fs;
+
+/// Loads the ROM of the second player with its own battery save. The file has to differ from
+/// the first player's, otherwise both would write the same `.sav`
+pub fn load_second(rom_file: &str, link_rom: &str) -> Result<Gameboy, String> {
+    let same_file = match (fs::canonicalize(rom_file), fs::canonicalize(link_rom)) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => rom_file == link_rom,
+    };
+    if same_file {
+        return Err(format!(
+            "--link-rom needs a copy of {} so each player keeps their own save",
+            rom_file
+        ));
+    }
+
+    let mut gameboy = Gameboy::new();
+    let header = gameboy.load(link_rom).map_err(|e| e.to_string())?;
+    crate::warn_header_checksums(&header);
+    Ok(gameboy)
+}
+
+/// Runs both Game Boys next to each other until the window closes. Only the first one is heard,
+/// save states and rewind need a single Game Boy
+pub fn run_linked(
+    pair: &mut LinkedGameboys,
+    video: &mut WindowVideo,
+    audio: &mut dyn AudioSink,
+    mut inputs: [&mut dyn InputSource; 2],
+) {
+    pair.gameboys_mut()[0].set_capture_channels(audio.wants_channel_samples());
+    let mut warned = false;
+    loop {
+        let states = inputs.each_mut().map(|input| input.poll());
+        if states.iter().any(|state| state.quit) {
+            break;
+        }
+        if !warned && (states[0].hotkey.is_some() || states[0].rewind) {
+            warned = true;
+            eprintln!("Save states, rewind and the debugger are not available with two Game Boys");
+        }
+
+        let [first, second] = pair.gameboys_mut();
+        first.set_sample_rate(audio.sample_rate());
+        first.set_buttons(states[0].buttons);
+        second.set_buttons(states[1].buttons);
+
+        pair.run_frame();
+        for gameboy in pair.gameboys_mut() {
+            if let Some(e) = gameboy.take_save_error() {
+                eprintln!("{}", e);
+            }
+        }
+
+        let [first, second] = pair.gameboys_mut();
+        audio.queue_samples(first.audio_samples());
+        audio.queue_channel_samples(first.channel_samples());
+        first.clear_audio_samples();
+        second.clear_audio_samples();
+        video.draw(0, first.framebuffer());
+        video.draw(1, second.framebuffer());
+        video.show();
+    }
+
+    if let Err(e) = pair.flush_saves() {
+        eprintln!("{}", e);
+    }
+}
@@ -0,0 +1,32 @@
+#[cfg(not(efi))]
+pub mod debugger;
+#[cfg(not(efi))]
+pub mod gbs;
+#[cfg(not(efi))]
+pub mod gdb;
+#[cfg(efi)]
+pub mod gop;
+#[cfg(not(efi))]
+pub mod headless;
+#[cfg(not(efi))]
+pub mod link;
+#[cfg(not(efi))]
+pub mod linked;
+pub mod palette;
+#[cfg(not(efi))]
+pub mod png;
+#[cfg(not(efi))]
+pub mod printer;
+#[cfg(not(efi))]
+pub mod serial;
+#[cfg(not(efi))]
+pub mod speaker;
+#[cfg(not(efi))]
+pub mod wav;
+#[cfg(not(efi))]
+pub mod window;
+
+use core::time::Duration;
+
+/// Real time length of a frame, 70224 dot cycles at 4.194304 MHz
+pub const FRAME_DURATION: Duration = Duration::from_nanos(16_742_706);
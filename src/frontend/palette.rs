@@ -0,0 +1,37 @@
+use rustemu::PixelLevel;
+
+pub struct Palette {
+    zero: u32,
+    one: u32,
+    two: u32,
+    three: u32,
+}
+
+impl Palette {
+    pub fn default() -> Self {
+        Self {
+            zero: 0xE0F8D0,
+            one: 0x88C070,
+            two: 0x346856,
+            three: 0x081820,
+        }
+    }
+
+    pub fn new(z: u32, o: u32, t: u32, tr: u32) -> Self {
+        Self {
+            zero: z,
+            one: o,
+            two: t,
+            three: tr,
+        }
+    }
+
+    pub fn translate_palette(&self, pixel_level: PixelLevel) -> u32 {
+        match pixel_level {
+            PixelLevel::Zero => self.zero,
+            PixelLevel::One => self.one,
+            PixelLevel::Two => self.two,
+            PixelLevel::Three => self.three,
+        }
+    }
+}
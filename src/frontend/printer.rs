@@ -0,0 +1,94 @@
+use crate::frontend::png;
+use rustemu::{Gameboy, InputSource, InputState, PRINTER_WIDTH, PrintedPage, Printer};
+use std::fs;
+use std::path::PathBuf;
+
+// Shades 0-3 on paper, lightest to darkest
+const PAPER_SHADES: [u8; 4] = [0xFF, 0xAA, 0x55, 0x00];
+
+/// Makes the directory for the pages, before anything else is written so a bad path fails early
+pub fn create_dir(dir: &str) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir, e))
+}
+
+/// Saves every page the Game Boy Printer finishes as `print-NNN.png` in a directory, looking
+/// for new pages between frames
+pub struct PrinterOutput<I: InputSource> {
+    input: I,
+    printer: Option<Printer>,
+    dir: PathBuf,
+    next_number: u32,
+}
+
+impl<I: InputSource> PrinterOutput<I> {
+    /// Plugs a printer into the link port if there is a directory for its pages, otherwise the
+    /// input is passed through untouched. The directory has to exist, see `create_dir`
+    pub fn new(gameboy: &mut Gameboy, input: I, dir: Option<&str>) -> Self {
+        let printer = dir.map(|_| {
+            let printer = Printer::new();
+            gameboy.set_serial_device(printer.clone());
+            printer
+        });
+        Self {
+            input,
+            printer,
+            dir: PathBuf::from(dir.unwrap_or_default()),
+            next_number: 1,
+        }
+    }
+
+    /// The wrapped input source
+    pub fn input_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    // Earlier prints in the directory are kept
+    fn next_path(&mut self) -> PathBuf {
+        loop {
+            let path = self.dir.join(format!("print-{:03}.png", self.next_number));
+            self.next_number += 1;
+            if !path.exists() {
+                return path;
+            }
+        }
+    }
+
+    fn save_page(&mut self, page: &PrintedPage) -> Result<(), String> {
+        let path = self.next_path();
+        let pixels: Vec<u8> = page
+            .pixels
+            .iter()
+            .map(|&shade| PAPER_SHADES[shade as usize & 0x03])
+            .collect();
+        png::write_gray(&path, PRINTER_WIDTH, page.height, &pixels)?;
+        println!("Printed {}", path.display());
+        Ok(())
+    }
+
+    fn save_pages(&mut self) -> Result<(), String> {
+        let Some(printer) = self.printer.as_ref() else {
+            return Ok(());
+        };
+        for page in printer.take_pages() {
+            self.save_page(&page)?;
+        }
+        Ok(())
+    }
+
+    /// Saves what is on the paper, the last page is cut off wherever the printing stopped
+    pub fn finish(&mut self) -> Result<(), String> {
+        if let Some(printer) = self.printer.as_ref() {
+            printer.cut();
+        }
+        self.save_pages()
+    }
+}
+
+impl<I: InputSource> InputSource for PrinterOutput<I> {
+    fn poll(&mut self) -> InputState {
+        if let Err(e) = self.save_pages() {
+            eprintln!("{}", e);
+        }
+        self.input.poll()
+    }
+}
@@ -0,0 +1,92 @@
+use rustemu::{Gameboy, InputSource, InputState, SerialCapture};
+use std::io::{self, Write};
+
+// Blargg's test ROMs end their report with one of these
+const PASSED: &str = "Passed";
+const FAILED: &str = "Failed";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TestResult {
+    Passed,
+    Failed,
+}
+
+impl TestResult {
+    /// 0 when the ROM passed, 1 when it failed and 3 when it never said
+    pub fn exit_code(result: Option<TestResult>) -> i32 {
+        match result {
+            Some(TestResult::Passed) => 0,
+            Some(TestResult::Failed) => 1,
+            None => 3,
+        }
+    }
+}
+
+/// Watches the serial output between frames, echoing it to stdout and stopping the emulation
+/// once a test ROM reports its result
+pub struct SerialMonitor<I: InputSource> {
+    input: I,
+    capture: Option<SerialCapture>,
+    print: bool,
+    stop_on_result: bool,
+    printed: usize,
+    result: Option<TestResult>,
+}
+
+impl<I: InputSource> SerialMonitor<I> {
+    /// Plugs a capture into the link port if the output is printed or checked, otherwise the
+    /// input is passed through untouched
+    pub fn new(gameboy: &mut Gameboy, input: I, print: bool, stop_on_result: bool) -> Self {
+        let capture = (print || stop_on_result).then(SerialCapture::new);
+        if let Some(capture) = capture.as_ref() {
+            gameboy.set_serial_device(capture.clone());
+        }
+        Self {
+            input,
+            capture,
+            print,
+            stop_on_result,
+            printed: 0,
+            result: None,
+        }
+    }
+
+    fn update(&mut self) {
+        let Some(capture) = self.capture.as_ref() else {
+            return;
+        };
+        let text = capture.text();
+        if self.print && text.len() > self.printed {
+            let mut stdout = io::stdout();
+            let _ = stdout.write_all(&text.as_bytes()[self.printed..]);
+            let _ = stdout.flush();
+            self.printed = text.len();
+        }
+        if self.result.is_none() {
+            self.result = if text.contains(PASSED) {
+                Some(TestResult::Passed)
+            } else if text.contains(FAILED) {
+                Some(TestResult::Failed)
+            } else {
+                None
+            };
+        }
+    }
+
+    /// Picks up what was sent after the last frame. Returns the test result, if one was reported
+    pub fn finish(&mut self) -> Option<TestResult> {
+        self.update();
+        self.result
+    }
+}
+
+impl<I: InputSource> InputSource for SerialMonitor<I> {
+    fn poll(&mut self) -> InputState {
+        let mut state = self.input.poll();
+        self.update();
+        if self.stop_on_result && self.result.is_some() {
+            state.quit = true;
+        }
+        state
+    }
+}
@@ -0,0 +1,172 @@
+use rustemu::AudioSink;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Sound queued ahead of the device, the emulation waits while more than this is buffered
+#[cfg(feature = "audio")]
+const TARGET_LATENCY: Duration = Duration::from_millis(50);
+/// Largest change to the mixing rate, small enough that the pitch shift is inaudible
+const MAX_RATE_DEVIATION: f64 = 0.005;
+/// Longest wait for the device to make room before giving up on this frame
+const MAX_WAIT: Duration = Duration::from_millis(100);
+
+/// Ring buffer of stereo samples shared between the emulation and the audio callback
+#[derive(Clone, Default)]
+struct SampleQueue(Arc<Mutex<VecDeque<[f32; 2]>>>);
+
+impl SampleQueue {
+    fn lock(&self) -> MutexGuard<'_, VecDeque<[f32; 2]>> {
+        // A panic in the other thread leaves the samples intact
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn len(&self) -> usize {
+        self.lock().len()
+    }
+}
+
+/// Mixing rate that moves the buffer level towards `target`, above the device rate while the
+/// buffer runs low and below it while it runs full
+fn adjusted_rate(sample_rate: u32, buffered: usize, target: usize) -> u32 {
+    let level = buffered as f64 / target.max(1) as f64;
+    let deviation =
+        ((1.0 - level) * MAX_RATE_DEVIATION).clamp(-MAX_RATE_DEVIATION, MAX_RATE_DEVIATION);
+    (sample_rate as f64 * (1.0 + deviation)).round() as u32
+}
+
+/// Plays the sound on the default output device and paces the emulation by how fast the device
+/// consumes it
+pub struct Speaker {
+    queue: SampleQueue,
+    sample_rate: u32,
+    target_samples: usize,
+    silence: Vec<[f32; 2]>,
+    #[cfg(feature = "audio")]
+    _stream: cpal::Stream,
+}
+
+impl Speaker {
+    /// The default output device, `None` when built without the `audio` feature
+    #[cfg(feature = "audio")]
+    pub fn open() -> Result<Option<Self>, String> {
+        use crate::frontend::FRAME_DURATION;
+        use cpal::SampleFormat;
+        use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or("No audio output device")?;
+        let supported = device.default_output_config().map_err(|e| e.to_string())?;
+        let config = supported.config();
+
+        let queue = SampleQueue::default();
+        let stream = match supported.sample_format() {
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, queue.clone()),
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, queue.clone()),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, queue.clone()),
+            format => Err(format!("Unsupported sample format {}", format)),
+        }?;
+        stream.play().map_err(|e| e.to_string())?;
+
+        let sample_rate = config.sample_rate.0;
+        Ok(Some(Self {
+            queue,
+            sample_rate,
+            target_samples: (sample_rate as f64 * TARGET_LATENCY.as_secs_f64()) as usize,
+            silence: vec![[0.0; 2]; (sample_rate as f64 * FRAME_DURATION.as_secs_f64()) as usize],
+            _stream: stream,
+        }))
+    }
+
+    #[cfg(not(feature = "audio"))]
+    pub fn open() -> Result<Option<Self>, String> {
+        Ok(None)
+    }
+
+    /// Rate of the output device, without the adjustments made while playing
+    pub fn device_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl AudioSink for Speaker {
+    fn queue_samples(&mut self, samples: &[[f32; 2]]) {
+        // Frames without sound, like while rewinding, still take their time
+        let samples = if samples.is_empty() {
+            &self.silence
+        } else {
+            samples
+        };
+        self.queue.lock().extend(samples);
+
+        let start = Instant::now();
+        while self.queue.len() > self.target_samples {
+            if start.elapsed() > MAX_WAIT {
+                // The device stalled, drop the oldest sound rather than the emulation
+                let mut queue = self.queue.lock();
+                let excess = queue.len().saturating_sub(self.target_samples);
+                queue.drain(..excess);
+                break;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    fn sample_rate(&self) -> u32 {
+        adjusted_rate(self.sample_rate, self.queue.len(), self.target_samples)
+    }
+}
+
+#[cfg(feature = "audio")]
+fn build_stream<T>(
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+    queue: SampleQueue,
+) -> Result<cpal::Stream, String>
+where
+    T: cpal::SizedSample + cpal::FromSample<f32>,
+{
+    use cpal::traits::DeviceTrait;
+
+    let channels = config.channels as usize;
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let mut queue = queue.lock();
+                for frame in data.chunks_mut(channels) {
+                    // Silence while the emulation falls behind
+                    let [left, right] = queue.pop_front().unwrap_or([0.0; 2]);
+                    for (channel, output) in frame.iter_mut().enumerate() {
+                        let sample = match (channels, channel) {
+                            (1, _) => (left + right) / 2.0,
+                            (_, 0) => left,
+                            (_, 1) => right,
+                            _ => 0.0,
+                        };
+                        *output = T::from_sample(sample);
+                    }
+                }
+            },
+            |e| eprintln!("Audio playback error: {}", e),
+            None,
+        )
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frontend::speaker::*;
+
+    #[test]
+    fn test_adjusted_rate() {
+        assert_eq!(adjusted_rate(48_000, 2400, 2400), 48_000);
+        // An empty buffer speeds the mixing up, a full one slows it down
+        assert_eq!(adjusted_rate(48_000, 0, 2400), 48_240);
+        assert_eq!(adjusted_rate(48_000, 4800, 2400), 47_760);
+        assert_eq!(adjusted_rate(48_000, 100_000, 2400), 47_760);
+        assert!(adjusted_rate(48_000, 1200, 2400) > 48_000);
+    }
+}
@@ -0,0 +1,238 @@
+use rustemu::AudioSink;
+use std::fs::File;
+use std::io::{self, BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+/// Rate of recordings made without a playback device to follow
+pub const RECORDING_SAMPLE_RATE: u32 = 48_000;
+const BITS_PER_SAMPLE: u16 = 16;
+const HEADER_SIZE: u32 = 44;
+
+/// 16-bit PCM WAV file. The chunk sizes in the header are only correct after `finish`
+pub struct WavWriter {
+    file: BufWriter<File>,
+    channels: u16,
+    data_bytes: u32,
+}
+
+impl WavWriter {
+    pub fn create(path: &str, channels: u16, sample_rate: u32) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        write_header(&mut file, channels, sample_rate, 0)?;
+        Ok(Self {
+            file,
+            channels,
+            data_bytes: 0,
+        })
+    }
+
+    /// One sample per channel, clamped to -1.0..=1.0
+    pub fn write_frame(&mut self, frame: &[f32]) -> io::Result<()> {
+        debug_assert_eq!(frame.len(), self.channels as usize);
+        for &sample in frame {
+            let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            self.file.write_all(&sample.to_le_bytes())?;
+        }
+        self.data_bytes = self
+            .data_bytes
+            .saturating_add(self.channels as u32 * (BITS_PER_SAMPLE / 8) as u32);
+        Ok(())
+    }
+
+    /// Patches the chunk sizes into the header and flushes the file
+    pub fn finish(mut self) -> io::Result<()> {
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(
+            &(HEADER_SIZE - 8)
+                .saturating_add(self.data_bytes)
+                .to_le_bytes(),
+        )?;
+        self.file.seek(SeekFrom::Start(40))?;
+        self.file.write_all(&self.data_bytes.to_le_bytes())?;
+        self.file.flush()
+    }
+}
+
+fn write_header(
+    file: &mut impl Write,
+    channels: u16,
+    sample_rate: u32,
+    data_bytes: u32,
+) -> io::Result<()> {
+    let block_align = channels * (BITS_PER_SAMPLE / 8);
+    file.write_all(b"RIFF")?;
+    file.write_all(&(HEADER_SIZE - 8 + data_bytes).to_le_bytes())?;
+    file.write_all(b"WAVE")?;
+    file.write_all(b"fmt ")?;
+    file.write_all(&16u32.to_le_bytes())?;
+    // Uncompressed PCM
+    file.write_all(&1u16.to_le_bytes())?;
+    file.write_all(&channels.to_le_bytes())?;
+    file.write_all(&sample_rate.to_le_bytes())?;
+    file.write_all(&(sample_rate * block_align as u32).to_le_bytes())?;
+    file.write_all(&block_align.to_le_bytes())?;
+    file.write_all(&BITS_PER_SAMPLE.to_le_bytes())?;
+    file.write_all(b"data")?;
+    file.write_all(&data_bytes.to_le_bytes())
+}
+
+/// `out.wav` becomes `out.ch1.wav` for channel 1
+pub fn stem_path(path: &str, channel: usize) -> String {
+    let base = Path::new(path).with_extension("");
+    format!("{}.ch{}.wav", base.display(), channel)
+}
+
+/// Records the stereo mix to a WAV file, and optionally every channel to a mono stem next to it
+pub struct WavSink {
+    mix: WavWriter,
+    stems: Option<[WavWriter; 4]>,
+    sample_rate: u32,
+    // First write error, reported by `finish` since the sink interface cannot fail
+    error: Option<io::Error>,
+}
+
+impl WavSink {
+    pub fn create(path: &str, record_stems: bool, sample_rate: u32) -> Result<Self, String> {
+        let create = |path: &str, channels| {
+            WavWriter::create(path, channels, sample_rate)
+                .map_err(|e| format!("Failed to create {}: {}", path, e))
+        };
+
+        let mix = create(path, 2)?;
+        let stems = if record_stems {
+            let [ch1, ch2, ch3, ch4] =
+                [1, 2, 3, 4].map(|channel| create(&stem_path(path, channel), 1));
+            Some([ch1?, ch2?, ch3?, ch4?])
+        } else {
+            None
+        };
+
+        Ok(Self {
+            mix,
+            stems,
+            sample_rate,
+            error: None,
+        })
+    }
+
+    fn record_error(&mut self, result: io::Result<()>) {
+        if let Err(e) = result
+            && self.error.is_none()
+        {
+            self.error = Some(e);
+        }
+    }
+
+    pub fn finish(self) -> Result<(), String> {
+        let mut result = self.error.map_or(Ok(()), Err);
+        result = result.and(self.mix.finish());
+        for stem in self.stems.into_iter().flatten() {
+            result = result.and(stem.finish());
+        }
+        result.map_err(|e| format!("Failed to write audio recording: {}", e))
+    }
+}
+
+impl AudioSink for WavSink {
+    fn queue_samples(&mut self, samples: &[[f32; 2]]) {
+        if self.error.is_some() {
+            return;
+        }
+        let result = samples
+            .iter()
+            .try_for_each(|sample| self.mix.write_frame(sample));
+        self.record_error(result);
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn wants_channel_samples(&self) -> bool {
+        self.stems.is_some()
+    }
+
+    fn queue_channel_samples(&mut self, samples: &[[f32; 4]]) {
+        let Some(stems) = self.stems.as_mut() else {
+            return;
+        };
+        if self.error.is_some() {
+            return;
+        }
+        let result = samples.iter().try_for_each(|channels| {
+            stems
+                .iter_mut()
+                .zip(channels)
+                .try_for_each(|(stem, &sample)| stem.write_frame(&[sample]))
+        });
+        self.record_error(result);
+    }
+}
+
+/// Records the sound while another sink plays it, at the rate the player asks for
+pub struct RecordedPlayback<'a> {
+    pub playback: &'a mut dyn AudioSink,
+    pub recording: &'a mut WavSink,
+}
+
+impl AudioSink for RecordedPlayback<'_> {
+    fn queue_samples(&mut self, samples: &[[f32; 2]]) {
+        self.recording.queue_samples(samples);
+        self.playback.queue_samples(samples);
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.playback.sample_rate()
+    }
+
+    fn wants_channel_samples(&self) -> bool {
+        self.recording.wants_channel_samples()
+    }
+
+    fn queue_channel_samples(&mut self, samples: &[[f32; 4]]) {
+        self.recording.queue_channel_samples(samples);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::frontend::wav::*;
+
+    #[test]
+    fn test_wav_header() {
+        let path = std::env::temp_dir().join(format!("rustemu-test-{}.wav", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        let mut sink = WavSink::create(path, true, RECORDING_SAMPLE_RATE).unwrap();
+        sink.queue_samples(&[[0.0, 0.5], [1.0, -2.0], [-1.0, 0.0]]);
+        sink.queue_channel_samples(&[[0.0; 4], [0.25; 4], [0.0; 4]]);
+        sink.finish().unwrap();
+
+        let data = std::fs::read(path).unwrap();
+        assert_eq!(&data[0..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(data[4..8].try_into().unwrap()), 36 + 12);
+        assert_eq!(&data[8..16], b"WAVEfmt ");
+        assert_eq!(u16::from_le_bytes(data[22..24].try_into().unwrap()), 2);
+        assert_eq!(u32::from_le_bytes(data[24..28].try_into().unwrap()), 48_000);
+        assert_eq!(u32::from_le_bytes(data[40..44].try_into().unwrap()), 12);
+        assert_eq!(data.len(), 44 + 12);
+        // The second frame is clamped to full scale
+        assert_eq!(
+            i16::from_le_bytes(data[48..50].try_into().unwrap()),
+            i16::MAX
+        );
+        assert_eq!(
+            i16::from_le_bytes(data[50..52].try_into().unwrap()),
+            -i16::MAX
+        );
+
+        let stem = std::fs::read(stem_path(path, 2)).unwrap();
+        assert_eq!(u16::from_le_bytes(stem[22..24].try_into().unwrap()), 1);
+        assert_eq!(stem.len(), 44 + 6);
+
+        std::fs::remove_file(path).unwrap();
+        for channel in 1..=4 {
+            std::fs::remove_file(stem_path(path, channel)).unwrap();
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use crate::frontend::FRAME_DURATION;
+use crate::frontend::palette::Palette;
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
+use rustemu::{
+    Button, Hotkey, InputSource, InputState, PixelLevel, SCREEN_HEIGHT, SCREEN_WIDTH, VideoSink,
+};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Instant;
+
+const REWIND_KEY: Key = Key::Backspace;
+// F1-F4 save to slots 1-4, holding shift loads from them instead
+const SLOT_KEYS: [Key; 4] = [Key::F1, Key::F2, Key::F3, Key::F4];
+const DEBUG_KEY: Key = Key::F12;
+const KEY_MAP: [(Button, Key); 8] = [
+    (Button::A, Key::A),
+    (Button::B, Key::B),
+    (Button::Start, Key::Space),
+    (Button::Select, Key::E),
+    (Button::Up, Key::Up),
+    (Button::Down, Key::Down),
+    (Button::Left, Key::Left),
+    (Button::Right, Key::Right),
+];
+// The second player of a linked pair shares the keyboard
+const SECOND_KEY_MAP: [(Button, Key); 8] = [
+    (Button::A, Key::O),
+    (Button::B, Key::U),
+    (Button::Start, Key::Enter),
+    (Button::Select, Key::P),
+    (Button::Up, Key::I),
+    (Button::Down, Key::K),
+    (Button::Left, Key::J),
+    (Button::Right, Key::L),
+];
+
+/// minifb window, paced to the native frame rate unless the audio output sets the speed. Can
+/// show several screens side by side
+pub struct WindowVideo {
+    palette: Palette,
+    window: Rc<RefCell<Window>>,
+    screens: usize,
+    display: Vec<u32>,
+    rumble: Rc<Cell<bool>>,
+    rumble_shown: bool,
+    pace_frames: bool,
+    frame_start: Instant,
+}
+
+impl WindowVideo {
+    /// Window with room for `screens` screens next to each other, drawn with `draw`
+    pub fn new(palette: Palette, pace_frames: bool, screens: usize) -> Self {
+        let window_options = WindowOptions {
+            resize: false,
+            title: true,
+            scale: minifb::Scale::X4,
+            ..WindowOptions::default()
+        };
+        let window = Window::new(
+            "rustemu",
+            SCREEN_WIDTH * screens,
+            SCREEN_HEIGHT,
+            window_options,
+        )
+        .expect("Unable to open window");
+
+        Self {
+            palette,
+            window: Rc::new(RefCell::new(window)),
+            screens,
+            display: vec![0; SCREEN_WIDTH * screens * SCREEN_HEIGHT],
+            rumble: Rc::new(Cell::new(false)),
+            rumble_shown: false,
+            pace_frames,
+            frame_start: Instant::now(),
+        }
+    }
+
+    /// Keyboard input of this window
+    pub fn input(&self) -> WindowInput {
+        WindowInput {
+            window: self.window.clone(),
+            key_map: &KEY_MAP,
+        }
+    }
+
+    /// Keyboard input of the second player, only the buttons are used
+    pub fn second_input(&self) -> WindowInput {
+        WindowInput {
+            window: self.window.clone(),
+            key_map: &SECOND_KEY_MAP,
+        }
+    }
+
+    /// Shared flag the cartridge rumble callback writes the motor state to
+    pub fn rumble_indicator(&self) -> Rc<Cell<bool>> {
+        self.rumble.clone()
+    }
+
+    fn update_rumble(&mut self) {
+        let rumble = self.rumble.get();
+        if rumble == self.rumble_shown {
+            return;
+        }
+        self.rumble_shown = rumble;
+
+        self.window.borrow_mut().set_title(if rumble {
+            "rustemu (rumble)"
+        } else {
+            "rustemu"
+        });
+    }
+
+    /// Puts a frame into one of the screens, it shows up with the next `show`
+    pub fn draw(&mut self, screen: usize, frame: &[PixelLevel; SCREEN_WIDTH * SCREEN_HEIGHT]) {
+        let rows = self.display.chunks_exact_mut(SCREEN_WIDTH * self.screens);
+        for (row, line) in rows.zip(frame.as_chunks::<SCREEN_WIDTH>().0) {
+            let row = &mut row[screen * SCREEN_WIDTH..(screen + 1) * SCREEN_WIDTH];
+            for (pixel, &level) in row.iter_mut().zip(line) {
+                *pixel = self.palette.translate_palette(level);
+            }
+        }
+    }
+
+    /// Updates the window with what was drawn and waits for the end of the frame
+    pub fn show(&mut self) {
+        self.update_rumble();
+
+        self.window
+            .borrow_mut()
+            .update_with_buffer(&self.display, SCREEN_WIDTH * self.screens, SCREEN_HEIGHT)
+            .expect("Something went wrong");
+
+        let elapsed = self.frame_start.elapsed();
+        if self.pace_frames && elapsed < FRAME_DURATION {
+            std::thread::sleep(FRAME_DURATION - elapsed);
+        }
+        self.frame_start = Instant::now();
+    }
+}
+
+impl VideoSink for WindowVideo {
+    fn present(&mut self, frame: &[PixelLevel; SCREEN_WIDTH * SCREEN_HEIGHT]) {
+        self.draw(0, frame);
+        self.show();
+    }
+
+    fn show_message(&mut self, message: &str) {
+        eprintln!("{}", message);
+    }
+}
+
+pub struct WindowInput {
+    window: Rc<RefCell<Window>>,
+    key_map: &'static [(Button, Key); 8],
+}
+
+impl WindowInput {
+    fn hotkey(window: &Window) -> Option<Hotkey> {
+        if window.is_key_pressed(DEBUG_KEY, KeyRepeat::No) {
+            return Some(Hotkey::Debug);
+        }
+        let slot = SLOT_KEYS
+            .iter()
+            .position(|&key| window.is_key_pressed(key, KeyRepeat::No))? as u8
+            + 1;
+        let shift = window.is_key_down(Key::LeftShift) || window.is_key_down(Key::RightShift);
+        Some(if shift {
+            Hotkey::LoadState(slot)
+        } else {
+            Hotkey::SaveState(slot)
+        })
+    }
+}
+
+impl InputSource for WindowInput {
+    fn poll(&mut self) -> InputState {
+        let window = self.window.borrow();
+        InputState {
+            buttons: self
+                .key_map
+                .iter()
+                .filter(|(_, key)| window.is_key_down(*key))
+                .fold(0, |pressed, (button, _)| pressed | *button as u8),
+            rewind: window.is_key_down(REWIND_KEY),
+            hotkey: Self::hotkey(&window),
+            quit: !window.is_open(),
+        }
+    }
+}
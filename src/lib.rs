@@ -0,0 +1,26 @@
+//! Game Boy (DMG) emulator core. `Gameboy` owns the whole machine and is driven by the caller one
+//! instruction or frame at a time, the window, input and pacing are left to the frontend
+#![cfg_attr(efi, no_std)]
+
+extern crate alloc;
+
+mod console;
+mod read_rom;
+mod save_ram;
+
+pub use console::cartridge::header::{CartridgeHeader, CartridgeType, CgbSupport, MapperKind};
+pub use console::cartridge::load_error::LoadError;
+pub use console::constants::{SCREEN_HEIGHT, SCREEN_WIDTH};
+pub use console::debugger::{
+    Breakpoint, DebugBreak, Registers, RunTo, WatchHit, WatchKind, Watchpoint,
+};
+pub use console::frontend::{AudioSink, Hotkey, InputSource, InputState, NullAudio, VideoSink};
+pub use console::gameboy::Gameboy;
+pub use console::gbs::{GbsHeader, GbsPlayer};
+pub use console::gui::gpu::PixelLevel;
+pub use console::gui::input::Button;
+pub use console::link_cable::LinkedGameboys;
+pub use console::printer::{PRINTER_WIDTH, PrintedPage, Printer};
+pub use console::rewind::RewindConfig;
+pub use console::serial::{NullSerial, SerialCapture, SerialDevice};
+pub use read_rom::read_file;
@@ -1,13 +1,26 @@
 #![cfg_attr(efi, no_main)]
 #![cfg_attr(efi, no_std)]
 
-use log::info;
-use console::gameboy::Gameboy;
+extern crate alloc;
+
+use frontend::palette::Palette;
+use rustemu::{Gameboy, NullAudio};
+#[cfg(not(efi))]
+use frontend::printer::{self, PrinterOutput};
+#[cfg(not(efi))]
+use frontend::serial::{SerialMonitor, TestResult};
+#[cfg(not(efi))]
+use frontend::speaker::Speaker;
+#[cfg(not(efi))]
+use frontend::wav::{RECORDING_SAMPLE_RATE, RecordedPlayback, WavSink};
+#[cfg(not(efi))]
+use rustemu::{AudioSink, CartridgeHeader, LinkedGameboys, LoadError, RewindConfig};
 
 mod arg_parse;
-mod console;
-mod read_rom;
+mod frontend;
 
+#[cfg(efi)]
+use log::info;
 #[cfg(efi)]
 use uefi::{entry, Status};
 
@@ -20,8 +33,16 @@ fn main() -> Status {
     let mut gameboy = Gameboy::new();
 
     info!("Opening file: {}", "default.gb");
-    gameboy.load(&"default.gb");
-    gameboy.run();
+    match gameboy.load("default.gb") {
+        Ok(header) => info!("Loaded {}", header.title),
+        Err(e) => {
+            info!("{}", e);
+            return Status::UNSUPPORTED;
+        }
+    }
+
+    let mut video = frontend::gop::GopVideo::new(Palette::default());
+    gameboy.run(&mut video, &mut NullAudio, &mut frontend::gop::ConsoleInput);
 
     Status::SUCCESS
 }
@@ -30,24 +51,226 @@ fn main() -> Status {
 fn main() {
     use std::process::exit;
 
-    let (rom_file, palette) = match arg_parse::args::parse_args() {
-        Ok((palette_opt, Some(f))) => (f, palette_opt),
-        Ok((_, None)) => {
-            eprintln!("No romfile selected");
+    let args = match arg_parse::args::parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            exit(2);
+        }
+    };
+    if let Some(gbs_file) = args.gbs.as_deref() {
+        if let Err(e) = frontend::gbs::render_gbs(gbs_file, &args) {
+            eprintln!("{}", e);
+            exit(1);
+        }
+        return;
+    }
+
+    let Some(rom_file) = args.rom_file.as_deref() else {
+        eprintln!("No romfile selected");
+        exit(1);
+    };
+
+    if args.info {
+        let header = rustemu::read_file(rom_file).and_then(|data| {
+            CartridgeHeader::parse(&data).ok_or(LoadError::TruncatedHeader { len: data.len() })
+        });
+        match header {
+            Ok(header) => println!("{}", header),
+            Err(e) => {
+                report_load_error(rom_file, &e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    if args.headless {
+        match frontend::headless::run_headless(rom_file, &args) {
+            Ok(result) if args.test_result => exit(TestResult::exit_code(result)),
+            Ok(_) => {}
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
+
+    let mut gameboy = Gameboy::new();
+
+    let mut rewind_config = RewindConfig::default();
+    if let Some(budget_bytes) = args.rewind_budget_bytes {
+        rewind_config.budget_bytes = budget_bytes;
+    }
+    if let Some(interval) = args.rewind_interval {
+        rewind_config.interval_frames = interval;
+    }
+    // Linked Game Boys never rewind, the other side would not go back with them
+    let link_cable = args.link_listen.is_some() || args.link_connect.is_some();
+    if rewind_config.budget_bytes > 0 && args.link_rom.is_none() && !link_cable {
+        gameboy.enable_rewind(rewind_config);
+    }
+
+    match gameboy.load(rom_file) {
+        Ok(header) => warn_header_checksums(&header),
+        Err(e) => {
+            report_load_error(rom_file, &e);
+            exit(1);
+        }
+    }
+
+    let second_gameboy = match args.link_rom.as_deref() {
+        Some(link_rom) => match frontend::linked::load_second(rom_file, link_rom) {
+            Ok(gameboy) => Some(gameboy),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+
+    // Connecting blocks until the other side shows up, so the ROM is checked first
+    match frontend::link::open_link(&args) {
+        Ok(Some(link)) => gameboy.set_serial_device(link),
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{}", e);
             exit(1);
         }
+    }
+    let mut debugger = match frontend::debugger::open_debugger(&args) {
+        Ok(debugger) => debugger,
         Err(e) => {
             eprintln!("{}", e);
-            exit(2);
+            exit(1);
+        }
+    };
+
+    // Without a sound device the window keeps the pace with a timer
+    let mut speaker = match Speaker::open() {
+        Ok(speaker) => speaker,
+        Err(e) => {
+            eprintln!("No audio playback: {}", e);
+            None
+        }
+    };
+
+    if let Some(dir) = args.printer.as_deref()
+        && let Err(e) = printer::create_dir(dir)
+    {
+        eprintln!("{}", e);
+        exit(1);
+    }
+
+    // A recording follows the rate of the device it is played on
+    let recording_rate = speaker
+        .as_ref()
+        .map_or(RECORDING_SAMPLE_RATE, Speaker::device_rate);
+    let mut recording = match args.record_audio.as_deref() {
+        Some(path) => match WavSink::create(path, args.record_stems, recording_rate) {
+            Ok(recording) => Some(recording),
+            Err(e) => {
+                eprintln!("{}", e);
+                exit(1);
+            }
+        },
+        None => None,
+    };
+    let pace_frames = speaker.is_none();
+    let mut null_audio = NullAudio;
+    let mut recorded_playback;
+    let audio: &mut dyn AudioSink = match (speaker.as_mut(), recording.as_mut()) {
+        (Some(playback), Some(recording)) => {
+            recorded_playback = RecordedPlayback {
+                playback,
+                recording,
+            };
+            &mut recorded_playback
         }
+        (Some(playback), None) => playback,
+        (None, Some(recording)) => recording,
+        (None, None) => &mut null_audio,
     };
 
-    let mut gameboy = match palette {
-        Some([z, o, t, tr]) => Gameboy::new_with_pal(z, o, t, tr),
-        None => Gameboy::new(),
+    // The window only opens once the ROM is known to be playable
+    let palette = match args.palette {
+        Some([z, o, t, tr]) => Palette::new(z, o, t, tr),
+        None => Palette::default(),
+    };
+    let screens = if second_gameboy.is_some() { 2 } else { 1 };
+    let mut video = frontend::window::WindowVideo::new(palette, pace_frames, screens);
+    let input = SerialMonitor::new(
+        &mut gameboy,
+        video.input(),
+        args.serial_stdout,
+        args.test_result,
+    );
+    let mut input = PrinterOutput::new(&mut gameboy, input, args.printer.as_deref());
+    let rumble_indicator = video.rumble_indicator();
+    gameboy.set_rumble_callback(move |motor_on| rumble_indicator.set(motor_on));
+    let test_result = match second_gameboy {
+        Some(second_gameboy) => {
+            let mut second_input = video.second_input();
+            let mut pair = LinkedGameboys::new(gameboy, second_gameboy);
+            frontend::linked::run_linked(
+                &mut pair,
+                &mut video,
+                audio,
+                [&mut input, &mut second_input],
+            );
+            None
+        }
+        None => {
+            frontend::debugger::run(
+                &mut gameboy,
+                &mut video,
+                audio,
+                &mut input,
+                debugger.as_mut(),
+                link_cable,
+            );
+            input.input_mut().finish()
+        }
     };
 
+    // The recording is finished first so it stays playable when saving the prints fails
+    let recording_result = recording.map_or(Ok(()), |recording| recording.finish());
+    for result in [recording_result, input.finish()] {
+        if let Err(e) = result {
+            eprintln!("{}", e);
+            exit(1);
+        }
+    }
+    if args.test_result {
+        exit(TestResult::exit_code(test_result));
+    }
+}
+
+#[cfg(not(efi))]
+fn report_load_error(rom_file: &str, error: &LoadError) {
+    eprintln!("Error: {}", error);
+    match error {
+        LoadError::NotFound { .. } => {
+            eprintln!("Relative paths are resolved against the current directory")
+        }
+        LoadError::Io { .. } => {}
+        LoadError::TruncatedHeader { .. } => {
+            eprintln!("{} does not look like a Game Boy ROM", rom_file)
+        }
+        LoadError::UnsupportedMapper(_) => {
+            eprintln!("Supported cartridge types: ROM only, MBC1, MBC2, MBC3, MBC5 and HuC1")
+        }
+    }
+}
 
-    gameboy.load(&rom_file);
-    gameboy.run();
-}
\ No newline at end of file
+#[cfg(not(efi))]
+fn warn_header_checksums(header: &CartridgeHeader) {
+    if !header.header_checksum_valid() {
+        eprintln!("Warning: header checksum mismatch, real hardware would refuse this ROM");
+    }
+    if !header.global_checksum_valid() {
+        eprintln!("Warning: global checksum mismatch");
+    }
+}
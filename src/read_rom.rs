@@ -1,61 +1,88 @@
-use crate::console::constants::CARTRIDGE_SIZE;
-
-pub fn read_file(cartridge_path: &str) -> [u8; CARTRIDGE_SIZE] {
-    let mut data = [0u8; CARTRIDGE_SIZE];
+use crate::console::cartridge::load_error::LoadError;
+use alloc::string::String;
+use alloc::vec::Vec;
 
+/// Reads a whole ROM file. Relative paths are resolved against the working directory, on UEFI
+/// against the root of the boot volume
+pub fn read_file(cartridge_path: &str) -> Result<Vec<u8>, LoadError> {
     #[cfg(not(efi))]
-    {
-        use std::fs;
-        use std::path::Path;
-        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join(cartridge_path);
-        let rom_data = fs::read(path).expect("Failed to read file");
-        let len = rom_data.len().min(CARTRIDGE_SIZE);
-        data[..len].copy_from_slice(&rom_data[..len]);
-    }
+    let data = std::fs::read(cartridge_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => LoadError::NotFound {
+            path: String::from(cartridge_path),
+        },
+        _ => LoadError::Io {
+            path: String::from(cartridge_path),
+            reason: e.to_string(),
+        },
+    });
 
     #[cfg(efi)]
-    {
+    let data = {
+        use alloc::{format, vec};
         use log::info;
         use uefi::boot;
-        use uefi::prelude::*;
         use uefi::proto::loaded_image::LoadedImage;
         use uefi::proto::media::file::*;
         use uefi::proto::media::fs::SimpleFileSystem;
+        use uefi::{CString16, Status};
 
         list_efi_root();
 
-        let loaded_image =
-            boot::open_protocol_exclusive::<LoadedImage>(boot::image_handle()).unwrap();
-        let device_handle = loaded_image.device().unwrap();
-        let mut fs = boot::open_protocol_exclusive::<SimpleFileSystem>(device_handle).unwrap();
-        let mut root_dir = fs.open_volume().unwrap();
-
-        info!("oppening file: {}", cartridge_path);
-
-        let file_handle = root_dir
-            .open(
-                cstr16!("default.gb"),
-                FileMode::Read,
-                FileAttribute::empty(),
-            )
-            .expect("default.gb not found, make sure it's in the USB root");
-
-        let mut file = file_handle
-            .into_regular_file()
-            .expect("default.gb exists but is not a regular file");
-
-        let mut info_buf = [0u8; 128];
-        let info = file.get_info::<FileInfo>(&mut info_buf).unwrap();
-        let file_size = info.file_size() as usize;
-
-        if file_size > CARTRIDGE_SIZE {
-            info!("File too large: {}", file_size);
-            boot::stall(5_000_000_000);
-            panic!("ROM size exceeds CARTRIDGE_SIZE");
-        }
-
-        file.read(&mut data[..file_size]).unwrap();
-    }
+        let io_error = |reason: String| LoadError::Io {
+            path: String::from(cartridge_path),
+            reason,
+        };
+
+        (|| -> Result<Vec<u8>, LoadError> {
+            let loaded_image = boot::open_protocol_exclusive::<LoadedImage>(boot::image_handle())
+                .map_err(|e| io_error(format!("{:?}", e.status())))?;
+            let device_handle = loaded_image
+                .device()
+                .ok_or_else(|| io_error(String::from("boot volume not found")))?;
+            let mut fs = boot::open_protocol_exclusive::<SimpleFileSystem>(device_handle)
+                .map_err(|e| io_error(format!("{:?}", e.status())))?;
+            let mut root_dir = fs
+                .open_volume()
+                .map_err(|e| io_error(format!("{:?}", e.status())))?;
+
+            info!("oppening file: {}", cartridge_path);
+
+            let file_name = CString16::try_from(cartridge_path)
+                .map_err(|_| io_error(String::from("invalid file name")))?;
+            let file_handle = root_dir
+                .open(&file_name, FileMode::Read, FileAttribute::empty())
+                .map_err(|e| match e.status() {
+                    Status::NOT_FOUND => LoadError::NotFound {
+                        path: String::from(cartridge_path),
+                    },
+                    _ => io_error(format!("{:?}", e.status())),
+                })?;
+
+            let mut file = file_handle
+                .into_regular_file()
+                .ok_or_else(|| io_error(String::from("not a regular file")))?;
+
+            let mut info_buf = [0u8; 128];
+            let info = file
+                .get_info::<FileInfo>(&mut info_buf)
+                .map_err(|e| io_error(format!("{:?}", e.status())))?;
+            let file_size = info.file_size() as usize;
+
+            let mut data = vec![0u8; file_size];
+            let mut read = 0;
+            while read < file_size {
+                let count = file
+                    .read(&mut data[read..])
+                    .map_err(|e| io_error(format!("{:?}", e.status())))?;
+                if count == 0 {
+                    break;
+                }
+                read += count;
+            }
+            data.truncate(read);
+            Ok(data)
+        })()
+    };
 
     data
 }
@@ -0,0 +1,176 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Battery save file of the ROM at `rom_path`, the ROM path with a `.sav` extension
+pub fn save_path(rom_path: &str) -> String {
+    #[cfg(not(efi))]
+    let path = {
+        let path = std::path::Path::new(rom_path).with_extension("sav");
+        String::from(path.to_string_lossy())
+    };
+
+    #[cfg(efi)]
+    let path = {
+        let _ = rom_path;
+        String::from("default.sav")
+    };
+
+    path
+}
+
+/// Save state slot of the ROM at `rom_path`, the ROM path with a `.ss<slot>` extension
+pub fn state_path(rom_path: &str, slot: u8) -> String {
+    #[cfg(not(efi))]
+    let path = {
+        let path = std::path::Path::new(rom_path).with_extension(alloc::format!("ss{}", slot));
+        String::from(path.to_string_lossy())
+    };
+
+    #[cfg(efi)]
+    let path = {
+        let _ = rom_path;
+        alloc::format!("default.ss{}", slot)
+    };
+
+    path
+}
+
+pub fn read_save(save_path: &str) -> Option<Vec<u8>> {
+    #[cfg(not(efi))]
+    let data = std::fs::read(save_path).ok();
+
+    #[cfg(efi)]
+    let data = with_efi_root(|root_dir| {
+        use alloc::vec;
+        use uefi::CString16;
+        use uefi::proto::media::file::*;
+
+        let file_name = CString16::try_from(save_path).ok()?;
+        let mut file = root_dir
+            .open(&file_name, FileMode::Read, FileAttribute::empty())
+            .ok()?
+            .into_regular_file()?;
+
+        let mut info_buf = [0u8; 128];
+        let file_size = file.get_info::<FileInfo>(&mut info_buf).ok()?.file_size() as usize;
+
+        let mut data = vec![0u8; file_size];
+        let mut read = 0;
+        while read < file_size {
+            match file.read(&mut data[read..]) {
+                Ok(count) if count > 0 => read += count,
+                _ => break,
+            }
+        }
+        data.truncate(read);
+        Some(data)
+    });
+
+    data
+}
+
+pub fn write_save(save_path: &str, data: &[u8]) -> Result<(), String> {
+    #[cfg(not(efi))]
+    let result = {
+        // Write to a temporary file first so a crash mid write cannot corrupt the save
+        let tmp_path = alloc::format!("{}.tmp", save_path);
+        std::fs::write(&tmp_path, data)
+            .and_then(|_| std::fs::rename(&tmp_path, save_path))
+            .map_err(|e| alloc::format!("Failed to write save file {}: {}", save_path, e))
+    };
+
+    #[cfg(efi)]
+    let result = {
+        let written = with_efi_root(|root_dir| {
+            use uefi::CString16;
+            use uefi::proto::media::file::*;
+
+            let file_name = CString16::try_from(save_path).ok()?;
+            let open = |root_dir: &mut Directory| {
+                root_dir
+                    .open(&file_name, FileMode::CreateReadWrite, FileAttribute::empty())
+                    .ok()?
+                    .into_regular_file()
+            };
+
+            let mut file = open(root_dir)?;
+
+            // Overwrite in place so there is never a moment without a save on the volume,
+            // only recreate the file when leftover bytes from a bigger save would remain
+            let mut info_buf = [0u8; 128];
+            let file_size = file.get_info::<FileInfo>(&mut info_buf).ok()?.file_size() as usize;
+            if file_size > data.len() {
+                file.delete().ok()?;
+                file = open(root_dir)?;
+            }
+
+            file.write(data).ok()?;
+            file.flush().ok()?;
+            Some(())
+        });
+
+        written.ok_or_else(|| alloc::format!("Failed to write save file {}", save_path))
+    };
+
+    result
+}
+
+#[cfg(efi)]
+fn with_efi_root<T>(
+    f: impl FnOnce(&mut uefi::proto::media::file::Directory) -> Option<T>,
+) -> Option<T> {
+    use uefi::boot;
+    use uefi::proto::loaded_image::LoadedImage;
+    use uefi::proto::media::fs::SimpleFileSystem;
+
+    let loaded_image = boot::open_protocol_exclusive::<LoadedImage>(boot::image_handle()).ok()?;
+    let device_handle = loaded_image.device()?;
+    let mut fs = boot::open_protocol_exclusive::<SimpleFileSystem>(device_handle).ok()?;
+    let mut root_dir = fs.open_volume().ok()?;
+    f(&mut root_dir)
+}
+
+/// Seconds since the UNIX epoch, used to keep cartridge clocks running while the emulator is off
+pub fn unix_time() -> u64 {
+    #[cfg(not(efi))]
+    let timestamp = {
+        use std::time::{SystemTime, UNIX_EPOCH};
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_secs())
+    };
+
+    #[cfg(efi)]
+    let timestamp = uefi::runtime::get_time().map_or(0, |time| {
+        let days = days_from_civil(time.year() as i64, time.month() as i64, time.day() as i64);
+        days.max(0) as u64 * 86400
+            + time.hour() as u64 * 3600
+            + time.minute() as u64 * 60
+            + time.second() as u64
+    });
+
+    timestamp
+}
+
+/// Days between 1970-01-01 and the given date of the proleptic Gregorian calendar
+#[cfg(any(efi, test))]
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::save_ram::days_from_civil;
+
+    #[test]
+    fn test_days_from_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11017);
+        assert_eq!(days_from_civil(2024, 2, 29), 19782);
+    }
+}